@@ -2,19 +2,66 @@
 -- Solo lee datos de X-Plane y los escribe a JSON
 -- La app Rust maneja toda la lógica
 
+-- Ruta del archivo puente. Dejar en nil para usar la variable HOLDING_TRAINER_BRIDGE
+-- o la carpeta por defecto; la app tiene que apuntar al mismo archivo.
+local BRIDGE_FILE_OVERRIDE = nil
+
+-- Misma resolución que default_bridge_path() en la app Rust
+local function bridge_file_path()
+    local override = BRIDGE_FILE_OVERRIDE or os.getenv("HOLDING_TRAINER_BRIDGE")
+    if override and override ~= "" then
+        return override
+    end
+
+    -- Windows usa "\\" como separador de directorios
+    if package.config:sub(1, 1) == "\\" then
+        local temp_dir = os.getenv("TEMP") or os.getenv("TMP")
+        if not temp_dir or temp_dir == "" then
+            local localappdata = os.getenv("LOCALAPPDATA") or os.getenv("USERPROFILE") or "C:"
+            temp_dir = localappdata .. "\\Temp"
+        end
+        return temp_dir .. "\\xplane_data.json"
+    end
+
+    local runtime_dir = os.getenv("XDG_RUNTIME_DIR")
+    if runtime_dir and runtime_dir ~= "" then
+        return runtime_dir .. "/xplane_data.json"
+    end
+    return "/tmp/xplane_data.json"
+end
+
 -- Función para escribir datos a archivo JSON
 function write_xplane_data()
     -- Leer datos de X-Plane
     local vor_freq = get("sim/cockpit2/radios/actuators/nav1_frequency_hz") or 0
     local vor_id = get("sim/cockpit2/radios/indicators/nav1_nav_id") or ""
+    -- ADF1 en kHz (para esperas sobre NDB)
+    local adf_freq = get("sim/cockpit2/radios/actuators/adf1_frequency_hz") or 0
 
     local lat = get("sim/flightmodel/position/latitude") or 0
     local lon = get("sim/flightmodel/position/longitude") or 0
-    local alt = get("sim/flightmodel/position/elevation") or 0
+    local alt_m = get("sim/flightmodel/position/elevation") or 0
+    local alt = alt_m * 3.28084  -- Convertir metros a pies
     local heading = get("sim/cockpit2/gauges/indicators/heading_AHARS_deg_mag_pilot") or 0
     local groundspeed_ms = get("sim/flightmodel/position/groundspeed") or 0
     local groundspeed = groundspeed_ms * 1.94384  -- Convertir m/s a nudos
 
+    -- Datos extra (la app los trata como opcionales)
+    local heading_true = get("sim/flightmodel/position/psi") or 0
+    local track = get("sim/flightmodel/position/hpath") or 0
+    local ias = get("sim/flightmodel/position/indicated_airspeed") or 0
+    local bank = get("sim/flightmodel/position/phi") or 0
+    local vertical_speed = get("sim/flightmodel/position/vh_ind_fpm") or 0
+    -- Viento en el avión: velocidad en m/s hacia donde sopla (x al este, z al sur)
+    local wind_x = get("sim/weather/wind_now_x_msc") or 0
+    local wind_z = get("sim/weather/wind_now_z_msc") or 0
+    local wind_speed = math.sqrt(wind_x * wind_x + wind_z * wind_z) * 1.94384
+    -- Dirección de la que viene, en grados verdaderos
+    local wind_direction = math.deg(math.atan2(-wind_x, wind_z)) % 360
+    -- Variación positiva al este: magnético = verdadero - variación
+    local magnetic_variation = (heading_true - heading + 540) % 360 - 180
+    track = track % 360
+
     -- Buscar VOR si hay uno sintonizado
     local vor_lat = 0
     local vor_lon = 0
@@ -30,14 +77,28 @@ function write_xplane_data()
         end
     end
 
-    -- Escribir JSON con datos básicos de X-Plane
-    local temp_dir = os.getenv("TEMP") or os.getenv("TMP")
-    if not temp_dir then
-        local localappdata = os.getenv("LOCALAPPDATA") or os.getenv("USERPROFILE")
-        temp_dir = localappdata .. "\\Temp"
+    -- Buscar el NDB sintonizado en el ADF1; el ident sale de la base de datos de X-Plane
+    local adf_id = ""
+    local adf_lat = 0
+    local adf_lon = 0
+
+    if adf_freq > 0 then
+        local ndb_ref = XPLMFindNavAid(nil, nil, lat, lon, adf_freq, xplm_Nav_NDB)
+        if ndb_ref ~= nil and ndb_ref ~= 0 then
+            local nav_type, n_lat, n_lon, ndb_height, ndb_freq_check, ndb_heading, ndb_id, ndb_name = XPLMGetNavAidInfo(ndb_ref)
+            if n_lat and n_lon and ndb_id then
+                adf_id = ndb_id
+                adf_lat = n_lat
+                adf_lon = n_lon
+            end
+        end
     end
-    local file_path = temp_dir .. "\\xplane_data.json"
-    local file = io.open(file_path, "w")
+
+    -- Escribir JSON con datos básicos de X-Plane en un archivo temporal y renombrarlo,
+    -- así la app nunca lee un archivo a medio escribir
+    local file_path = bridge_file_path()
+    local temp_path = file_path .. ".tmp"
+    local file = io.open(temp_path, "w")
     if file then
         file:write("{\n")
         file:write(string.format('  "vor_id": "%s",\n', vor_id))
@@ -48,9 +109,36 @@ function write_xplane_data()
         file:write(string.format('  "aircraft_lon": %.6f,\n', lon))
         file:write(string.format('  "aircraft_alt": %.1f,\n', alt))
         file:write(string.format('  "aircraft_heading": %.1f,\n', heading))
-        file:write(string.format('  "aircraft_groundspeed": %.1f\n', groundspeed))
+        file:write(string.format('  "aircraft_groundspeed": %.1f,\n', groundspeed))
+        file:write(string.format('  "aircraft_heading_true": %.1f,\n', heading_true))
+        file:write(string.format('  "aircraft_track": %.1f,\n', track))
+        file:write(string.format('  "magnetic_variation": %.1f,\n', magnetic_variation))
+        file:write(string.format('  "indicated_airspeed": %.1f,\n', ias))
+        file:write(string.format('  "bank_angle": %.1f,\n', bank))
+        file:write(string.format('  "vertical_speed": %.0f,\n', vertical_speed))
+        file:write(string.format('  "wind_direction": %.0f,\n', wind_direction))
+        file:write(string.format('  "wind_speed": %.0f,\n', wind_speed))
+        file:write(string.format('  "adf_id": "%s",\n', adf_id))
+        file:write(string.format('  "adf_freq": %d,\n', adf_freq))
+        file:write(string.format('  "adf_lat": %.6f,\n', adf_lat))
+        file:write(string.format('  "adf_lon": %.6f\n', adf_lon))
         file:write("}\n")
         file:close()
+
+        -- En Linux y macOS os.rename reemplaza el destino de forma atómica. En Windows falla
+        -- si el destino existe, así que hay que borrarlo antes y el cambio deja de ser atómico:
+        -- entre las dos llamadas la app puede no encontrar el archivo (lo trata como un hueco
+        -- y conserva la última muestra). Si la app lo tiene abierto y el renombrado falla, se
+        -- reintenta; si sigue fallando se pierde esta muestra y el temporal se reescribe en
+        -- el siguiente ciclo.
+        if not os.rename(temp_path, file_path) then
+            for _ = 1, 5 do
+                os.remove(file_path)
+                if os.rename(temp_path, file_path) then
+                    break
+                end
+            end
+        end
     end
 end
 
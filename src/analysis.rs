@@ -0,0 +1,421 @@
+use crate::calculations::{angle_difference, calculate_bearing, calculate_distance, normalize_angle};
+use crate::data::{EntryKind, HoldingPattern, LegLength, TrackPoint};
+use crate::geometry::hold_frame;
+
+const FIX_PASSAGE_RADIUS_NM: f64 = 1.0;
+const MIN_SEGMENT_NM: f64 = 0.05;
+const TURN_DETECT_DEG: f64 = 20.0;
+const STABLE_RATE_DEG_S: f64 = 1.0;
+const STABLE_MIN_SECONDS: f64 = 10.0;
+const TEARDROP_OFFSET_MIN: f64 = 15.0;
+const TEARDROP_OFFSET_MAX: f64 = 60.0;
+const INTERCEPT_CROSS_NM: f64 = 0.5;
+const INTERCEPT_TRACK_DEG: f64 = 30.0;
+
+#[derive(Debug, Clone)]
+pub struct FlownEntryReport {
+    pub flown_entry: Option<EntryKind>,
+    pub matched: bool,
+    pub first_turn_right: Option<bool>,
+    pub outbound_offset: Option<f64>,
+    pub outbound_time: Option<f64>,
+    pub intercepted: bool,
+    pub deviations: Vec<String>,
+}
+
+struct Sample {
+    time: f64,
+    along: f64,
+    cross: f64,
+    track: f64,
+}
+
+pub fn analyze_flown_entry(holding: &HoldingPattern) -> FlownEntryReport {
+    let (fix_lat, fix_lon) = (holding.fix_lat, holding.fix_lon);
+    let mut report = FlownEntryReport {
+        flown_entry: None,
+        matched: false,
+        first_turn_right: None,
+        outbound_offset: None,
+        outbound_time: None,
+        intercepted: false,
+        deviations: Vec::new(),
+    };
+
+    let passage = match find_fix_passage(&holding.track_points, fix_lat, fix_lon) {
+        Some(index) => index,
+        None => {
+            report.deviations.push("Fix passage not detected".to_string());
+            return report;
+        }
+    };
+
+    let samples = build_samples(&holding.track_points[passage..], holding, fix_lat, fix_lon);
+    if samples.len() < 3 {
+        report.deviations.push("Not enough track after fix passage".to_string());
+        return report;
+    }
+
+    let turn_start = match detect_turn(&samples, 0) {
+        Some((index, right)) => {
+            report.first_turn_right = Some(right);
+            index
+        }
+        None => {
+            report.deviations.push("No turn after fix passage".to_string());
+            return report;
+        }
+    };
+
+    let (leg_start, leg_end) = match find_stable_leg(&samples, turn_start) {
+        Some(range) => range,
+        None => {
+            report.deviations.push("No stable outbound leg yet".to_string());
+            return report;
+        }
+    };
+
+    let side = if holding.right_turns { 1.0 } else { -1.0 };
+    let leg_track = mean_track(&samples[leg_start..=leg_end]);
+    let offset = -side * angle_difference(leg_track, holding.outbound_course);
+    let leg_cross = samples[leg_start..=leg_end].iter().map(|s| s.cross).sum::<f64>() / (leg_end - leg_start + 1) as f64;
+    let leg_time = samples[leg_end].time - samples[leg_start].time;
+
+    report.outbound_offset = Some(offset);
+    report.outbound_time = Some(leg_time);
+
+    let flown = if (TEARDROP_OFFSET_MIN..=TEARDROP_OFFSET_MAX).contains(&offset) {
+        EntryKind::Teardrop
+    } else if leg_cross >= 0.0 {
+        EntryKind::Direct
+    } else {
+        EntryKind::Parallel
+    };
+    report.flown_entry = Some(flown);
+    report.matched = holding.correct_entry.is_some_and(|entry| entry.allows(flown));
+
+    let turn_name = |right: bool| if right { "RIGHT" } else { "LEFT" };
+
+    if let Some(correct_entry) = holding.correct_entry {
+        if !report.matched {
+            report.deviations.push(format!("Flew {} entry, expected {}", flown.label(), correct_entry.label()));
+        }
+    }
+
+    if flown == EntryKind::Direct && report.first_turn_right != Some(holding.right_turns) {
+        report.deviations.push(format!("Turned {} after the fix, direct entry turns {}",
+            turn_name(!holding.right_turns), turn_name(holding.right_turns)));
+    }
+
+    if flown == EntryKind::Teardrop && (offset - 30.0).abs() > 10.0 {
+        report.deviations.push(format!("Teardrop offset {:.0}° (expected 30°)", offset));
+    }
+
+    let expected_time = match holding.leg {
+        LegLength::Time(seconds) => Some(seconds),
+        LegLength::Distance(_) => None,
+    };
+    if let Some(expected) = expected_time {
+        if flown != EntryKind::Direct && (leg_time - expected).abs() > 15.0 {
+            report.deviations.push(format!("Outbound leg {:.0}s (expected {:.0}s)", leg_time, expected));
+        }
+    }
+
+    if let Some((_, right)) = detect_turn(&samples, leg_end) {
+        let expected_right = if flown == EntryKind::Parallel { !holding.right_turns } else { holding.right_turns };
+        if right != expected_right {
+            report.deviations.push(format!("Turned {} to return, {} entry turns {}",
+                turn_name(right), flown.label().to_lowercase(), turn_name(expected_right)));
+        }
+    }
+
+    report.intercepted = samples[leg_end..].iter().any(|s| {
+        s.along < 0.0
+            && s.cross.abs() <= INTERCEPT_CROSS_NM
+            && angle_difference(s.track, holding.inbound_course).abs() <= INTERCEPT_TRACK_DEG
+    });
+    if !report.intercepted {
+        report.deviations.push("Inbound course not intercepted yet".to_string());
+    }
+
+    report
+}
+
+pub fn find_fix_passage(points: &[TrackPoint], fix_lat: f64, fix_lon: f64) -> Option<usize> {
+    let mut best: Option<(usize, f64)> = None;
+
+    for (index, point) in points.iter().enumerate() {
+        let distance = calculate_distance(point.lat, point.lon, fix_lat, fix_lon);
+        match best {
+            Some((_, best_distance)) if distance < best_distance => best = Some((index, distance)),
+            None => best = Some((index, distance)),
+            Some((best_index, best_distance)) => {
+                if best_distance <= FIX_PASSAGE_RADIUS_NM && distance > best_distance + MIN_SEGMENT_NM {
+                    return Some(best_index);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn build_samples(points: &[TrackPoint], holding: &HoldingPattern, fix_lat: f64, fix_lon: f64) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    let mut last: Option<&TrackPoint> = None;
+
+    for point in points {
+        if let Some(previous) = last {
+            if calculate_distance(previous.lat, previous.lon, point.lat, point.lon) < MIN_SEGMENT_NM {
+                continue;
+            }
+            let (along, cross) = hold_frame(point.lat, point.lon, fix_lat, fix_lon, holding.inbound_course, holding.right_turns);
+            samples.push(Sample {
+                time: point.time,
+                along,
+                cross,
+                track: calculate_bearing(previous.lat, previous.lon, point.lat, point.lon),
+            });
+        }
+        last = Some(point);
+    }
+    samples
+}
+
+fn detect_turn(samples: &[Sample], from: usize) -> Option<(usize, bool)> {
+    let mut accumulated = 0.0;
+    for index in (from + 1)..samples.len() {
+        accumulated += angle_difference(samples[index].track, samples[index - 1].track);
+        if accumulated.abs() >= TURN_DETECT_DEG {
+            return Some((index, accumulated > 0.0));
+        }
+    }
+    None
+}
+
+fn find_stable_leg(samples: &[Sample], from: usize) -> Option<(usize, usize)> {
+    let mut start: Option<usize> = None;
+
+    for index in (from + 1)..samples.len() {
+        let dt = (samples[index].time - samples[index - 1].time).max(0.1);
+        let rate = angle_difference(samples[index].track, samples[index - 1].track).abs() / dt;
+
+        if rate <= STABLE_RATE_DEG_S {
+            let leg_start = *start.get_or_insert(index - 1);
+            let ends_here = index + 1 == samples.len() || {
+                let next_dt = (samples[index + 1].time - samples[index].time).max(0.1);
+                angle_difference(samples[index + 1].track, samples[index].track).abs() / next_dt > STABLE_RATE_DEG_S
+            };
+            if ends_here && samples[index].time - samples[leg_start].time >= STABLE_MIN_SECONDS {
+                return Some((leg_start, index));
+            }
+        } else {
+            start = None;
+        }
+    }
+    None
+}
+
+fn mean_track(samples: &[Sample]) -> f64 {
+    let (x, y) = samples.iter().fold((0.0, 0.0), |(x, y), s| {
+        let rad = s.track.to_radians();
+        (x + rad.sin(), y + rad.cos())
+    });
+    normalize_angle(x.atan2(y).to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculations::{calculate_entry_type, destination_point};
+    use crate::data::{EntryRules, EntryType};
+
+    const FIX: (f64, f64) = (40.0, -3.0);
+    const GROUNDSPEED: f64 = 120.0;
+    const STEP_S: f64 = 2.0;
+
+    // Flies a no-wind track at 120 kt and standard rate, one point every two seconds.
+    struct Pilot {
+        lat: f64,
+        lon: f64,
+        heading: f64,
+        time: f64,
+        points: Vec<TrackPoint>,
+    }
+
+    impl Pilot {
+        // Starts on `heading` with the fix `seconds` ahead.
+        fn arriving(heading: f64, seconds: f64) -> Self {
+            let (lat, lon) = destination_point(FIX.0, FIX.1, heading + 180.0, GROUNDSPEED * seconds / 3600.0);
+            let mut pilot = Self { lat, lon, heading, time: 0.0, points: Vec::new() };
+            pilot.record();
+            pilot
+        }
+
+        fn record(&mut self) {
+            self.points.push(TrackPoint { lat: self.lat, lon: self.lon, time: self.time, alt: 8000.0, heading: self.heading, groundspeed: GROUNDSPEED, bank: None, indicated_airspeed: None });
+        }
+
+        fn step(&mut self) {
+            (self.lat, self.lon) = destination_point(self.lat, self.lon, self.heading, GROUNDSPEED * STEP_S / 3600.0);
+            self.time += STEP_S;
+            self.record();
+        }
+
+        fn fly(&mut self, seconds: f64) {
+            for _ in 0..(seconds / STEP_S).round() as usize {
+                self.step();
+            }
+        }
+
+        fn turn(&mut self, right: bool, to: f64) {
+            loop {
+                let remaining = if right { normalize_angle(to - self.heading) } else { normalize_angle(self.heading - to) };
+                if remaining < 0.5 {
+                    break;
+                }
+                let change = remaining.min(3.0 * STEP_S);
+                self.heading = normalize_angle(if right { self.heading + change } else { self.heading - change });
+                self.step();
+            }
+        }
+
+        fn fly_until(&mut self, holding: &HoldingPattern, done: impl Fn(f64, f64) -> bool) {
+            for _ in 0..300 {
+                let (along, cross) = hold_frame(self.lat, self.lon, FIX.0, FIX.1, holding.inbound_course, holding.right_turns);
+                if done(along, cross) {
+                    return;
+                }
+                self.step();
+            }
+            panic!("condition never reached");
+        }
+    }
+
+    // Inbound 090° with right turns: DIRECT for arrival headings 000-180, TEARDROP
+    // 180-250 and PARALLEL 250-360.
+    fn hold(arrival_heading: f64, rules: EntryRules) -> HoldingPattern {
+        HoldingPattern {
+            fix_lat: FIX.0,
+            fix_lon: FIX.1,
+            inbound_course: 90.0,
+            outbound_course: 270.0,
+            right_turns: true,
+            leg: LegLength::Time(60.0),
+            correct_entry: Some(calculate_entry_type(arrival_heading, 90.0, true, rules)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recognises_a_direct_entry() {
+        let mut holding = hold(90.0, EntryRules::Icao);
+        let mut pilot = Pilot::arriving(90.0, 120.0);
+        pilot.fly(120.0);
+        pilot.turn(true, 270.0);
+        pilot.fly(60.0);
+        pilot.turn(true, 90.0);
+        pilot.fly(60.0);
+        holding.track_points = pilot.points;
+
+        let report = analyze_flown_entry(&holding);
+        assert_eq!(report.flown_entry, Some(EntryKind::Direct));
+        assert!(report.matched);
+        assert_eq!(report.first_turn_right, Some(true));
+        assert!(report.outbound_offset.unwrap().abs() < 3.0);
+        assert!(report.intercepted);
+        assert!(report.deviations.is_empty(), "{:?}", report.deviations);
+    }
+
+    #[test]
+    fn recognises_a_teardrop_entry() {
+        let mut holding = hold(200.0, EntryRules::Icao);
+        let mut pilot = Pilot::arriving(200.0, 120.0);
+        pilot.fly(120.0);
+        pilot.turn(true, 240.0);
+        pilot.fly(60.0);
+        pilot.turn(true, 90.0);
+        pilot.fly(60.0);
+        holding.track_points = pilot.points;
+
+        let report = analyze_flown_entry(&holding);
+        assert_eq!(report.flown_entry, Some(EntryKind::Teardrop));
+        assert!(report.matched);
+        assert!((report.outbound_offset.unwrap() - 30.0).abs() < 3.0);
+        assert!((report.outbound_time.unwrap() - 60.0).abs() < 5.0);
+        assert!(report.intercepted);
+        assert!(report.deviations.is_empty(), "{:?}", report.deviations);
+    }
+
+    #[test]
+    fn recognises_a_parallel_entry() {
+        let mut holding = hold(330.0, EntryRules::Icao);
+        let mut pilot = Pilot::arriving(330.0, 120.0);
+        pilot.fly(120.0);
+        pilot.turn(false, 270.0);
+        pilot.fly(60.0);
+        // The turn ends on the holding side; cut back onto the inbound course.
+        pilot.turn(false, 45.0);
+        pilot.fly_until(&holding, |_, cross| cross <= 0.2);
+        pilot.turn(true, 90.0);
+        pilot.fly(30.0);
+        holding.track_points = pilot.points;
+
+        let report = analyze_flown_entry(&holding);
+        assert_eq!(report.flown_entry, Some(EntryKind::Parallel));
+        assert!(report.matched);
+        assert_eq!(report.first_turn_right, Some(false));
+        assert!(report.intercepted);
+        assert!(report.deviations.is_empty(), "{:?}", report.deviations);
+
+        // The same track when a direct entry was due.
+        holding.correct_entry = Some(EntryType::Direct);
+        let report = analyze_flown_entry(&holding);
+        assert!(!report.matched);
+        assert_eq!(report.deviations, ["Flew PARALLEL entry, expected DIRECT"]);
+    }
+
+    #[test]
+    fn accepts_either_entry_at_a_sector_boundary() {
+        // 178° is 2° inside DIRECT, so ICAO allows a teardrop and FAA doesn't.
+        let mut pilot = Pilot::arriving(178.0, 120.0);
+        pilot.fly(120.0);
+        pilot.turn(true, 240.0);
+        pilot.fly(60.0);
+        pilot.turn(true, 90.0);
+        pilot.fly(60.0);
+
+        let mut icao = hold(178.0, EntryRules::Icao);
+        assert!(matches!(icao.correct_entry, Some(EntryType::Ambiguous { .. })));
+        icao.track_points = pilot.points.clone();
+        let report = analyze_flown_entry(&icao);
+        assert_eq!(report.flown_entry, Some(EntryKind::Teardrop));
+        assert!(report.matched);
+
+        let mut faa = hold(178.0, EntryRules::Faa);
+        faa.track_points = pilot.points;
+        let report = analyze_flown_entry(&faa);
+        assert!(!report.matched);
+        assert_eq!(report.deviations[0], "Flew TEARDROP entry, expected DIRECT");
+    }
+
+    #[test]
+    fn reports_what_is_missing_from_an_incomplete_track() {
+        let mut holding = hold(90.0, EntryRules::Icao);
+        let mut pilot = Pilot::arriving(90.0, 120.0);
+        pilot.fly(60.0);
+        holding.track_points = pilot.points.clone();
+        let report = analyze_flown_entry(&holding);
+        assert_eq!(report.flown_entry, None);
+        assert_eq!(report.deviations, ["Fix passage not detected"]);
+
+        pilot.fly(60.0);
+        pilot.turn(true, 270.0);
+        pilot.fly(4.0);
+        holding.track_points = pilot.points;
+        let report = analyze_flown_entry(&holding);
+        assert_eq!(report.flown_entry, None);
+        assert!(!report.matched);
+        assert_eq!(report.first_turn_right, Some(true));
+        assert_eq!(report.deviations, ["No stable outbound leg yet"]);
+    }
+}
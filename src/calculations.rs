@@ -1,3 +1,5 @@
+use crate::data::{DmeLimit, EntryKind, EntryRules, EntryType, HoldingFix, HoldingSide, LegLength, Wind};
+
 pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     let r = 3440.065;
     let lat1_rad = lat1.to_radians();
@@ -23,33 +25,449 @@ pub fn calculate_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     (bearing + 360.0) % 360.0
 }
 
-pub fn calculate_entry_type(start_heading: f64, inbound_course: f64, right_turns: bool) -> String {
-    let relative_to_inbound = (start_heading - inbound_course + 360.0) % 360.0;
-    let margin = 5.0;
-
-    if right_turns {
-        if relative_to_inbound >= (90.0 - margin) && relative_to_inbound <= (90.0 + margin) {
-            "DIRECT/TEARDROP".to_string()
-        } else if relative_to_inbound >= (160.0 - margin) && relative_to_inbound <= (160.0 + margin) {
-            "TEARDROP/PARALLEL".to_string()
-        } else if relative_to_inbound >= 270.0 || relative_to_inbound < (90.0 - margin) {
-            "DIRECT".to_string()
-        } else if relative_to_inbound > (90.0 + margin) && relative_to_inbound < (160.0 - margin) {
-            "TEARDROP".to_string()
-        } else {
-            "PARALLEL".to_string()
+pub fn destination_point(lat: f64, lon: f64, bearing: f64, distance_nm: f64) -> (f64, f64) {
+    let bearing_rad = bearing.to_radians();
+    let angular_distance = distance_nm / 3440.065;
+    let lat_rad = lat.to_radians();
+    let lon_rad = lon.to_radians();
+
+    let new_lat_rad = (lat_rad.sin() * angular_distance.cos() +
+                      lat_rad.cos() * angular_distance.sin() * bearing_rad.cos()).asin();
+
+    let new_lon_rad = lon_rad +
+                     (bearing_rad.sin() * angular_distance.sin() * lat_rad.cos())
+                     .atan2(angular_distance.cos() - lat_rad.sin() * new_lat_rad.sin());
+
+    (new_lat_rad.to_degrees(), new_lon_rad.to_degrees())
+}
+
+// Intersection of two great-circle radials (Veness); None if they diverge or coincide.
+pub fn radial_intersection(lat1: f64, lon1: f64, radial1: f64, lat2: f64, lon2: f64, radial2: f64) -> Option<(f64, f64)> {
+    use std::f64::consts::PI;
+
+    let (phi1, lambda1) = (lat1.to_radians(), lon1.to_radians());
+    let (phi2, lambda2) = (lat2.to_radians(), lon2.to_radians());
+    let (theta13, theta23) = (radial1.to_radians(), radial2.to_radians());
+
+    let delta12 = 2.0 * (((phi2 - phi1) / 2.0).sin().powi(2)
+        + phi1.cos() * phi2.cos() * ((lambda2 - lambda1) / 2.0).sin().powi(2)).sqrt().asin();
+    if delta12.abs() < 1e-12 {
+        return None;
+    }
+
+    let theta_a = ((phi2.sin() - phi1.sin() * delta12.cos()) / (delta12.sin() * phi1.cos())).clamp(-1.0, 1.0).acos();
+    let theta_b = ((phi1.sin() - phi2.sin() * delta12.cos()) / (delta12.sin() * phi2.cos())).clamp(-1.0, 1.0).acos();
+
+    let (theta12, theta21) = if (lambda2 - lambda1).sin() > 0.0 {
+        (theta_a, 2.0 * PI - theta_b)
+    } else {
+        (2.0 * PI - theta_a, theta_b)
+    };
+
+    let wrap = |angle: f64| (angle + PI).rem_euclid(2.0 * PI) - PI;
+    let alpha1 = wrap(theta13 - theta12);
+    let alpha2 = wrap(theta21 - theta23);
+
+    if alpha1.sin() == 0.0 && alpha2.sin() == 0.0 {
+        return None;
+    }
+    if alpha1.sin() * alpha2.sin() < 0.0 {
+        return None;
+    }
+
+    let alpha3 = (-alpha1.cos() * alpha2.cos() + alpha1.sin() * alpha2.sin() * delta12.cos()).clamp(-1.0, 1.0).acos();
+    let delta13 = (delta12.sin() * alpha1.sin() * alpha2.sin()).atan2(alpha2.cos() + alpha1.cos() * alpha3.cos());
+
+    let phi3 = (phi1.sin() * delta13.cos() + phi1.cos() * delta13.sin() * theta13.cos()).clamp(-1.0, 1.0).asin();
+    let delta_lambda13 = (theta13.sin() * delta13.sin() * phi1.cos()).atan2(delta13.cos() - phi1.sin() * phi3.sin());
+
+    Some((phi3.to_degrees(), (lambda1 + delta_lambda13).to_degrees()))
+}
+
+pub fn resolve_fix(fix: &HoldingFix) -> Option<(f64, f64)> {
+    match fix {
+        HoldingFix::Station(station) => Some((station.lat, station.lon)),
+        HoldingFix::RadialDme { station, radial, distance } => {
+            Some(destination_point(station.lat, station.lon, magnetic_to_true(*radial, station.declination), *distance))
+        }
+        HoldingFix::Intersection { first, first_radial, second, second_radial } => radial_intersection(
+            first.lat,
+            first.lon,
+            magnetic_to_true(*first_radial, first.declination),
+            second.lat,
+            second.lon,
+            magnetic_to_true(*second_radial, second.declination),
+        ),
+        HoldingFix::Waypoint { lat, lon, .. } => Some((*lat, *lon)),
+    }
+}
+
+// Radials and published courses are magnetic, referenced to the station's declination
+// (east positive); bearings on the map and in the geometry are true.
+pub fn magnetic_to_true(course: f64, declination: f64) -> f64 {
+    normalize_angle(course + declination)
+}
+
+pub fn true_to_magnetic(course: f64, declination: f64) -> f64 {
+    normalize_angle(course - declination)
+}
+
+// Where an ADF needle points: the bearing to the station measured clockwise from the nose.
+pub fn relative_bearing(heading: f64, bearing_to_station: f64) -> f64 {
+    normalize_angle(bearing_to_station - heading)
+}
+
+// Distance legs at a DME-equipped fix on the holding radial end at a DME limit
+// rather than after a fixed along-track distance.
+pub fn dme_limit(fix: &HoldingFix, outbound_course: f64, leg: LegLength) -> Option<DmeLimit> {
+    let leg_nm = match leg {
+        LegLength::Distance(nm) => nm,
+        LegLength::Time(_) => return None,
+    };
+
+    let (station, fix_distance, side) = match fix {
+        HoldingFix::Station(station) => (station, 0.0, HoldingSide::AwayFromStation),
+        HoldingFix::RadialDme { station, radial, distance } => {
+            let side = if angle_difference(outbound_course, magnetic_to_true(*radial, station.declination)).abs() < 90.0 {
+                HoldingSide::AwayFromStation
+            } else {
+                HoldingSide::TowardStation
+            };
+            (station, *distance, side)
         }
+        _ => return None,
+    };
+
+    let distance = match side {
+        HoldingSide::AwayFromStation => fix_distance + leg_nm,
+        HoldingSide::TowardStation => fix_distance - leg_nm,
+    };
+    if distance <= 0.0 {
+        return None;
+    }
+
+    Some(DmeLimit {
+        station_lat: station.lat,
+        station_lon: station.lon,
+        distance,
+        side,
+    })
+}
+
+pub const TEARDROP_SECTOR_WIDTH: f64 = 70.0;
+
+// An entry sector as a clockwise range of arrival headings. The map paints the
+// same range shifted by 180°, i.e. as bearings from the fix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntrySector {
+    pub kind: EntryKind,
+    pub start: f64,
+    pub width: f64,
+}
+
+impl EntrySector {
+    pub fn contains_heading(&self, heading: f64) -> bool {
+        normalize_angle(heading - self.start) < self.width
+    }
+
+    pub fn bearing_start(&self) -> f64 {
+        normalize_angle(self.start + 180.0)
+    }
+}
+
+// Sectors are returned clockwise starting with DIRECT; each sector's start is the
+// boundary shared with the previous one.
+pub fn entry_sectors(inbound_course: f64, right_turns: bool) -> [EntrySector; 3] {
+    let parallel_width = 180.0 - TEARDROP_SECTOR_WIDTH;
+    let (second, second_width, third, third_width) = if right_turns {
+        (EntryKind::Teardrop, TEARDROP_SECTOR_WIDTH, EntryKind::Parallel, parallel_width)
     } else {
-        if relative_to_inbound >= (90.0 - margin) && relative_to_inbound <= (90.0 + margin) {
-            "DIRECT/PARALLEL".to_string()
-        } else if relative_to_inbound >= (200.0 - margin) && relative_to_inbound <= (200.0 + margin) {
-            "PARALLEL/TEARDROP".to_string()
-        } else if relative_to_inbound >= 270.0 || relative_to_inbound < (90.0 - margin) {
-            "DIRECT".to_string()
-        } else if relative_to_inbound > (200.0 + margin) && relative_to_inbound < 270.0 {
-            "TEARDROP".to_string()
-        } else {
-            "PARALLEL".to_string()
+        (EntryKind::Parallel, parallel_width, EntryKind::Teardrop, TEARDROP_SECTOR_WIDTH)
+    };
+
+    [
+        EntrySector { kind: EntryKind::Direct, start: normalize_angle(inbound_course + 270.0), width: 180.0 },
+        EntrySector { kind: second, start: normalize_angle(inbound_course + 90.0), width: second_width },
+        EntrySector { kind: third, start: normalize_angle(inbound_course + 90.0 + second_width), width: third_width },
+    ]
+}
+
+pub fn calculate_entry_type(start_heading: f64, inbound_course: f64, right_turns: bool, rules: EntryRules) -> EntryType {
+    let sectors = entry_sectors(inbound_course, right_turns);
+    let tolerance = rules.boundary_tolerance();
+
+    for (index, sector) in sectors.iter().enumerate() {
+        let previous = sectors[(index + sectors.len() - 1) % sectors.len()];
+        let boundary_offset = angle_difference(start_heading, sector.start).abs();
+        if boundary_offset <= tolerance {
+            return EntryType::Ambiguous { first: previous.kind, second: sector.kind, boundary_offset };
         }
     }
+
+    sectors
+        .iter()
+        .find(|sector| sector.contains_heading(start_heading))
+        .map_or(EntryType::Direct, |sector| sector.kind.into())
+}
+
+pub fn normalize_angle(angle: f64) -> f64 {
+    ((angle % 360.0) + 360.0) % 360.0
+}
+
+pub fn angle_difference(a: f64, b: f64) -> f64 {
+    let diff = normalize_angle(a - b);
+    if diff > 180.0 { diff - 360.0 } else { diff }
+}
+
+pub fn wind_correction_angle(course: f64, true_airspeed: f64, wind: Wind) -> f64 {
+    if true_airspeed <= 0.0 || wind.speed <= 0.0 {
+        return 0.0;
+    }
+    let crosswind_ratio = wind.speed / true_airspeed * (wind.direction - course).to_radians().sin();
+    crosswind_ratio.clamp(-1.0, 1.0).asin().to_degrees()
+}
+
+pub fn headwind_component(course: f64, wind: Wind) -> f64 {
+    wind.speed * (wind.direction - course).to_radians().cos()
+}
+
+pub fn groundspeed_on_course(course: f64, true_airspeed: f64, wind: Wind) -> f64 {
+    let wca = wind_correction_angle(course, true_airspeed, wind);
+    (true_airspeed * wca.to_radians().cos() - headwind_component(course, wind)).max(0.0)
+}
+
+// Returns (track, groundspeed) flown when holding `heading` in the given wind.
+pub fn ground_track(heading: f64, true_airspeed: f64, wind: Wind) -> (f64, f64) {
+    let heading_rad = heading.to_radians();
+    let wind_to_rad = (wind.direction + 180.0).to_radians();
+
+    let east = true_airspeed * heading_rad.sin() + wind.speed * wind_to_rad.sin();
+    let north = true_airspeed * heading_rad.cos() + wind.speed * wind_to_rad.cos();
+
+    let groundspeed = (east * east + north * north).sqrt();
+    if groundspeed < 1e-6 {
+        return (normalize_angle(heading), 0.0);
+    }
+    (normalize_angle(east.atan2(north).to_degrees()), groundspeed)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WindCorrection {
+    pub inbound_wca: f64,
+    pub inbound_heading: f64,
+    pub inbound_groundspeed: f64,
+    pub outbound_wca: f64,
+    pub outbound_heading: f64,
+    pub outbound_groundspeed: f64,
+    pub outbound_time: f64,
+}
+
+// Inbound WCA is flown as computed; outbound uses the "triple the drift" rule.
+// Outbound timing is chosen so the inbound leg lasts `inbound_leg_time` seconds,
+// accounting for the along-track drift of both standard-rate turns.
+pub fn holding_wind_correction(inbound_course: f64, true_airspeed: f64, wind: Wind, inbound_leg_time: f64) -> WindCorrection {
+    let outbound_course = normalize_angle(inbound_course + 180.0);
+
+    let inbound_wca = wind_correction_angle(inbound_course, true_airspeed, wind);
+    let outbound_wca = -3.0 * inbound_wca;
+
+    let inbound_groundspeed = groundspeed_on_course(inbound_course, true_airspeed, wind);
+    let outbound_groundspeed = groundspeed_on_course(outbound_course, true_airspeed, wind);
+
+    let turn_time = 60.0;
+    let headwind = headwind_component(inbound_course, wind);
+    let outbound_time = if outbound_groundspeed > 0.0 {
+        ((inbound_groundspeed * inbound_leg_time - 2.0 * headwind * turn_time) / outbound_groundspeed).max(0.0)
+    } else {
+        inbound_leg_time
+    };
+
+    WindCorrection {
+        inbound_wca,
+        inbound_heading: normalize_angle(inbound_course + inbound_wca),
+        inbound_groundspeed,
+        outbound_wca,
+        outbound_heading: normalize_angle(outbound_course + outbound_wca),
+        outbound_groundspeed,
+        outbound_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headings() -> impl Iterator<Item = f64> {
+        (0..1440).map(|step| step as f64 * 0.25)
+    }
+
+    #[test]
+    fn sectors_cover_the_compass_once() {
+        for inbound in [0.0, 45.0, 90.0, 183.0, 270.0, 359.0] {
+            for right_turns in [true, false] {
+                let sectors = entry_sectors(inbound, right_turns);
+                assert_eq!(sectors.iter().map(|s| s.width).sum::<f64>(), 360.0);
+
+                for heading in headings() {
+                    let containing = sectors.iter().filter(|s| s.contains_heading(heading)).count();
+                    assert_eq!(containing, 1, "heading {} inbound {} right {}", heading, inbound, right_turns);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn classifier_matches_painted_sector() {
+        for inbound in [0.0, 45.0, 90.0, 183.0, 270.0, 359.0] {
+            for right_turns in [true, false] {
+                let sectors = entry_sectors(inbound, right_turns);
+
+                for heading in headings() {
+                    // The map paints each sector as bearings from the fix, starting at bearing_start().
+                    let bearing_from_fix = normalize_angle(heading + 180.0);
+                    let painted = sectors
+                        .iter()
+                        .find(|s| normalize_angle(bearing_from_fix - s.bearing_start()) < s.width)
+                        .unwrap();
+
+                    match calculate_entry_type(heading, inbound, right_turns, EntryRules::Faa) {
+                        EntryType::Ambiguous { first, second, boundary_offset } => {
+                            assert_eq!(boundary_offset, 0.0);
+                            assert!(painted.kind == first || painted.kind == second);
+                        }
+                        entry => assert_eq!(entry, EntryType::from(painted.kind), "heading {} inbound {}", heading, inbound),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tolerance_marks_boundaries_ambiguous() {
+        let rules = EntryRules::Icao;
+        for (heading, expected) in [
+            (92.0, (EntryKind::Direct, EntryKind::Teardrop)),
+            (157.0, (EntryKind::Teardrop, EntryKind::Parallel)),
+            (273.0, (EntryKind::Parallel, EntryKind::Direct)),
+        ] {
+            match calculate_entry_type(heading, 0.0, true, rules) {
+                EntryType::Ambiguous { first, second, .. } => assert_eq!((first, second), expected),
+                other => panic!("heading {} classified as {:?}", heading, other),
+            }
+        }
+        assert_eq!(calculate_entry_type(120.0, 0.0, true, rules), EntryType::Teardrop);
+        assert_eq!(calculate_entry_type(120.0, 0.0, false, rules), EntryType::Parallel);
+        assert_eq!(calculate_entry_type(240.0, 0.0, false, rules), EntryType::Teardrop);
+        assert_eq!(calculate_entry_type(0.0, 0.0, false, rules), EntryType::Direct);
+        assert_eq!(calculate_entry_type(92.0, 0.0, true, EntryRules::Jeppesen), calculate_entry_type(92.0, 0.0, true, rules));
+    }
+
+    #[test]
+    fn radials_are_flown_relative_to_station_declination() {
+        use crate::data::{FixStation, HoldingPattern, NavaidKind};
+
+        let station = FixStation { id: "ANC".to_string(), lat: 61.15, lon: -150.2, declination: 20.0, kind: NavaidKind::Vor };
+        let fix = HoldingFix::RadialDme { station: station.clone(), radial: 90.0, distance: 10.0 };
+        let (lat, lon) = resolve_fix(&fix).unwrap();
+        assert!((calculate_bearing(station.lat, station.lon, lat, lon) - 110.0).abs() < 0.1);
+
+        let mut holding = HoldingPattern { fix: fix.clone(), ..Default::default() };
+        holding.set_radial(90, fix.declination());
+        assert_eq!((holding.outbound_course, holding.inbound_course), (110.0, 290.0));
+        assert_eq!(true_to_magnetic(holding.inbound_course, holding.declination), 270.0);
+
+        let limit = dme_limit(&fix, holding.outbound_course, LegLength::Distance(4.0)).unwrap();
+        assert_eq!((limit.side, limit.distance), (HoldingSide::AwayFromStation, 14.0));
+    }
+
+    #[test]
+    fn ndb_entries_follow_the_bearing_to_the_station() {
+        use crate::data::{FixStation, HoldingPattern, NavaidKind};
+
+        let ndb = FixStation { id: "SZ".to_string(), lat: 47.39, lon: -122.39, declination: 15.0, kind: NavaidKind::Ndb };
+        let mut holding = HoldingPattern { fix: HoldingFix::Station(ndb), ..Default::default() };
+        holding.set_radial(180, 15.0);
+        assert!(holding.is_ndb_hold());
+
+        // Heading 120 with the needle 60 right of the nose: the station bears 180,
+        // so the entry is direct even though a crosswind has the track at 110.
+        holding.start_heading = 120.0;
+        holding.start_track = 110.0;
+        holding.start_relative_bearing = relative_bearing(120.0, 180.0);
+        assert_eq!(holding.start_relative_bearing, 60.0);
+        assert_eq!(holding.entry_reference(), 180.0);
+        assert_eq!(relative_bearing(350.0, 10.0), 20.0);
+    }
+
+    #[test]
+    fn corrects_into_a_crosswind() {
+        let from_the_right = Wind { direction: 180.0, speed: 20.0 };
+        let wca = wind_correction_angle(90.0, 120.0, from_the_right);
+        assert!((wca - (20.0f64 / 120.0).asin().to_degrees()).abs() < 1e-9);
+        assert!(wca > 0.0);
+        assert!((wind_correction_angle(90.0, 120.0, Wind { direction: 0.0, speed: 20.0 }) + wca).abs() < 1e-9);
+
+        // Holding the corrected heading keeps the aircraft on course.
+        let (track, groundspeed) = ground_track(90.0 + wca, 120.0, from_the_right);
+        assert!((track - 90.0).abs() < 1e-9);
+        assert!((groundspeed - groundspeed_on_course(90.0, 120.0, from_the_right)).abs() < 1e-9);
+        assert!((groundspeed - 120.0 * wca.to_radians().cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn headwind_and_tailwind_only_change_groundspeed() {
+        let headwind = Wind { direction: 360.0, speed: 20.0 };
+        let tailwind = Wind { direction: 180.0, speed: 20.0 };
+        for (wind, expected) in [(headwind, 100.0), (tailwind, 140.0)] {
+            assert!(wind_correction_angle(0.0, 120.0, wind).abs() < 1e-9);
+            assert!((groundspeed_on_course(0.0, 120.0, wind) - expected).abs() < 1e-9);
+            let (track, groundspeed) = ground_track(0.0, 120.0, wind);
+            assert!(angle_difference(track, 0.0).abs() < 1e-9);
+            assert!((groundspeed - expected).abs() < 1e-9);
+        }
+        assert_eq!(headwind_component(0.0, headwind), 20.0);
+    }
+
+    #[test]
+    fn triples_the_drift_and_times_the_outbound_leg() {
+        let crosswind = holding_wind_correction(90.0, 120.0, Wind { direction: 180.0, speed: 20.0 }, 60.0);
+        assert!((crosswind.outbound_wca + 3.0 * crosswind.inbound_wca).abs() < 1e-9);
+        assert!((crosswind.inbound_heading - (90.0 + crosswind.inbound_wca)).abs() < 1e-9);
+        assert!((crosswind.outbound_heading - (270.0 - 3.0 * crosswind.inbound_wca)).abs() < 1e-9);
+        // Same groundspeed both ways: the outbound leg lasts as long as the inbound one.
+        assert!((crosswind.outbound_time - 60.0).abs() < 1e-9);
+
+        // 20 kt on the nose inbound: 100 kt in, 140 kt out, and each turn drifts
+        // 20 kt for a minute towards the fix.
+        let headwind = holding_wind_correction(90.0, 120.0, Wind { direction: 90.0, speed: 20.0 }, 60.0);
+        assert_eq!((headwind.inbound_groundspeed, headwind.outbound_groundspeed), (100.0, 140.0));
+        assert!((headwind.outbound_time - (100.0 * 60.0 - 2.0 * 20.0 * 60.0) / 140.0).abs() < 1e-9);
+        assert!(headwind.inbound_wca.abs() < 1e-9 && headwind.outbound_wca.abs() < 1e-9);
+
+        let calm = holding_wind_correction(90.0, 120.0, Wind::default(), 60.0);
+        assert_eq!((calm.inbound_heading, calm.outbound_heading), (90.0, 270.0));
+        assert_eq!((calm.inbound_groundspeed, calm.outbound_groundspeed, calm.outbound_time), (120.0, 120.0, 60.0));
+        assert_eq!(ground_track(45.0, 120.0, Wind::default()), (45.0, 120.0));
+    }
+
+    #[test]
+    fn interpolates_wind_between_layers() {
+        use crate::data::{WindLayer, WindModel};
+
+        assert_eq!(WindModel::default().wind_at(5000.0), Wind::default());
+        let low = Wind { direction: 350.0, speed: 10.0 };
+        let high = Wind { direction: 10.0, speed: 30.0 };
+        // Layers may come in any order.
+        let model = WindModel {
+            layers: vec![WindLayer { altitude: 10000.0, wind: high }, WindLayer { altitude: 0.0, wind: low }],
+        };
+        assert_eq!(model.wind_at(-500.0), low);
+        assert_eq!(model.wind_at(12000.0), high);
+
+        // Halfway up the wind veers through north, not back round through south.
+        let middle = model.wind_at(5000.0);
+        assert!(angle_difference(middle.direction, 5.0).abs() < 0.1, "{:?}", middle);
+        assert!(middle.speed > 19.0 && middle.speed < 20.0);
+        assert_eq!(WindModel::uniform(high).wind_at(3000.0), high);
+    }
 }
@@ -0,0 +1,149 @@
+use crate::calculations::{calculate_bearing, calculate_distance, ground_track, relative_bearing};
+use crate::data::{HoldingPattern, TrackPoint, Wind, WindModel, XPlaneData};
+
+const CAPTURE_DISTANCE_NM: f64 = 5.0;
+
+// Shared by every telemetry source: captures the entry once the aircraft closes
+// within 5 NM of the fix heading towards it, then records the track. Headings are
+// taken true so they compare with the bearing to the fix and the hold's courses.
+pub fn process_sample(holding: &mut HoldingPattern, data: &XPlaneData, time: f64) {
+    if !holding.active || holding.fix_lat == 0.0 {
+        return;
+    }
+
+    let distance = calculate_distance(
+        data.aircraft_lat,
+        data.aircraft_lon,
+        holding.fix_lat,
+        holding.fix_lon,
+    );
+
+    if !holding.entry_captured && distance <= CAPTURE_DISTANCE_NM && distance < holding.last_distance {
+        let bearing_to_fix = calculate_bearing(
+            data.aircraft_lat,
+            data.aircraft_lon,
+            holding.fix_lat,
+            holding.fix_lon,
+        );
+
+        let heading_diff = ((data.aircraft_heading_true - bearing_to_fix + 360.0) % 360.0).abs();
+        let heading_diff = if heading_diff > 180.0 { 360.0 - heading_diff } else { heading_diff };
+
+        if heading_diff <= 90.0 {
+            holding.start_heading = data.aircraft_heading_true;
+            // The track actually flown when the source reports it; otherwise the one
+            // the hold's wind would give.
+            holding.start_track = if data.reports_attitude() {
+                data.aircraft_track
+            } else {
+                ground_track(data.aircraft_heading_true, holding.true_airspeed, holding.wind.wind_at(data.aircraft_alt)).0
+            };
+            holding.start_relative_bearing = relative_bearing(data.aircraft_heading_true, bearing_to_fix);
+            holding.entry_lat = data.aircraft_lat;
+            holding.entry_lon = data.aircraft_lon;
+            holding.entry_captured = true;
+        }
+    }
+
+    holding.last_distance = distance;
+
+    if holding.entry_captured {
+        holding.track_points.push(TrackPoint {
+            lat: data.aircraft_lat,
+            lon: data.aircraft_lon,
+            time,
+            alt: data.aircraft_alt,
+            heading: data.aircraft_heading_true,
+            groundspeed: data.aircraft_groundspeed,
+            bank: data.reports_attitude().then_some(data.bank_angle),
+            indicated_airspeed: data.reports_attitude().then_some(data.indicated_airspeed),
+        });
+    }
+}
+
+// Live holds fly in the simulator's wind, not the trainer's setting, so analysis,
+// scoring and the wind correction on the map use what the source reports.
+pub fn adopt_reported_wind(holding: &mut HoldingPattern, data: &XPlaneData) {
+    if data.reports_attitude() {
+        holding.wind = WindModel::uniform(Wind { direction: data.wind_direction, speed: data.wind_speed });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight_model::FlightModel;
+
+    // Flies `model` north for `seconds`, one sample a second from `start`.
+    fn fly(holding: &mut HoldingPattern, model: &mut FlightModel, start: f64, seconds: usize) {
+        let wind = Wind { direction: 270.0, speed: 15.0 };
+        let mut data = XPlaneData::default();
+        for second in 1..=seconds {
+            model.step(1.0, wind);
+            model.write_to(&mut data, wind);
+            process_sample(holding, &data, start + second as f64);
+        }
+    }
+
+    #[test]
+    fn captures_the_entry_and_resumes_from_a_rewound_sample() {
+        let mut holding = HoldingPattern { active: true, fix_lat: 40.0, fix_lon: -3.0, ..Default::default() };
+        let start = FlightModel::new(39.8667, -3.0, 5000.0, 0.0, 120.0);
+
+        // 8 NM out at 2 NM a minute: captured after a minute and a half.
+        let mut model = start.clone();
+        fly(&mut holding, &mut model, 0.0, 200);
+        assert!(holding.entry_captured);
+        assert_eq!(holding.start_heading, 0.0);
+        let first = &holding.track_points[0];
+        assert!((85.0..=95.0).contains(&first.time));
+        assert!(holding.track_points.iter().all(|point| point.bank == Some(0.0)));
+        let finish = holding.track_points.last().unwrap().clone();
+
+        // Nothing to go back to before the capture.
+        assert!(holding.truncated_at(first.time - 1.0).track_points.is_empty());
+
+        let mut rewound = holding.truncated_at(150.5);
+        let point = rewound.track_points.last().unwrap().clone();
+        assert_eq!(point.time, 150.0);
+        rewound.last_distance = calculate_distance(point.lat, point.lon, rewound.fix_lat, rewound.fix_lon);
+        let mut resumed = model.resume_from(&point);
+        fly(&mut rewound, &mut resumed, point.time, 50);
+
+        // Flying on from the sample retraces the original track.
+        assert_eq!(rewound.track_points.len(), holding.track_points.len());
+        assert!(rewound.track_points.windows(2).all(|pair| pair[1].time > pair[0].time));
+        let end = rewound.track_points.last().unwrap();
+        assert_eq!(end.time, finish.time);
+        assert!(calculate_distance(end.lat, end.lon, finish.lat, finish.lon) < 0.001);
+    }
+
+    #[test]
+    fn judges_the_entry_on_the_reported_track_and_wind() {
+        let sample = |indicated_airspeed: f64| XPlaneData {
+            aircraft_lat: 39.95,
+            aircraft_lon: -3.0,
+            aircraft_heading_true: 0.0,
+            aircraft_track: 10.0,
+            indicated_airspeed,
+            wind_direction: 280.0,
+            wind_speed: 25.0,
+            ..Default::default()
+        };
+
+        // Heading north, drifting right in a 25 kt westerly the trainer knows nothing about.
+        let mut holding = HoldingPattern { active: true, fix_lat: 40.0, fix_lon: -3.0, ..Default::default() };
+        adopt_reported_wind(&mut holding, &sample(120.0));
+        process_sample(&mut holding, &sample(120.0), 1.0);
+        assert!(holding.entry_captured);
+        assert_eq!((holding.start_heading, holding.start_track), (0.0, 10.0));
+        assert_eq!(holding.wind.wind_at(3000.0), Wind { direction: 280.0, speed: 25.0 });
+
+        // A bridge file without the richer fields: calm in the trainer, so track is heading.
+        let mut holding = HoldingPattern { active: true, fix_lat: 40.0, fix_lon: -3.0, ..Default::default() };
+        adopt_reported_wind(&mut holding, &sample(0.0));
+        process_sample(&mut holding, &sample(0.0), 1.0);
+        assert_eq!(holding.start_track, 0.0);
+        assert_eq!(holding.wind.wind_at(3000.0), Wind::default());
+    }
+}
@@ -1,4 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::calculations::{calculate_distance, magnetic_to_true, normalize_angle};
+use crate::magnetic;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XPlaneData {
@@ -9,8 +14,47 @@ pub struct XPlaneData {
     pub aircraft_lat: f64,
     pub aircraft_lon: f64,
     pub aircraft_alt: f64,
+    // Magnetic, as on the heading indicator.
     pub aircraft_heading: f64,
     pub aircraft_groundspeed: f64,
+    // Added later; older bridge files and recordings don't have them.
+    #[serde(default)]
+    pub aircraft_heading_true: f64,
+    #[serde(default)]
+    pub aircraft_track: f64,
+    // East positive: magnetic = true - variation.
+    #[serde(default)]
+    pub magnetic_variation: f64,
+    #[serde(default)]
+    pub indicated_airspeed: f64,
+    // Right wing down positive.
+    #[serde(default)]
+    pub bank_angle: f64,
+    // Feet per minute.
+    #[serde(default)]
+    pub vertical_speed: f64,
+    // Wind at the aircraft: direction it blows from (true) and knots.
+    #[serde(default)]
+    pub wind_direction: f64,
+    #[serde(default)]
+    pub wind_speed: f64,
+    // ADF1: the tuned NDB (kHz) and, once identified, where it is.
+    #[serde(default)]
+    pub adf_id: String,
+    #[serde(default)]
+    pub adf_freq: i32,
+    #[serde(default)]
+    pub adf_lat: f64,
+    #[serde(default)]
+    pub adf_lon: f64,
+}
+
+impl XPlaneData {
+    // Sources and bridge files that predate the richer fields leave them all at
+    // zero, and an aircraft in flight always shows some airspeed.
+    pub fn reports_attitude(&self) -> bool {
+        self.indicated_airspeed > 0.0
+    }
 }
 
 impl Default for XPlaneData {
@@ -25,6 +69,105 @@ impl Default for XPlaneData {
             aircraft_alt: 0.0,
             aircraft_heading: 0.0,
             aircraft_groundspeed: 0.0,
+            aircraft_heading_true: 0.0,
+            aircraft_track: 0.0,
+            magnetic_variation: 0.0,
+            indicated_airspeed: 0.0,
+            bank_angle: 0.0,
+            vertical_speed: 0.0,
+            wind_direction: 0.0,
+            wind_speed: 0.0,
+            adf_id: String::new(),
+            adf_freq: 0,
+            adf_lat: 0.0,
+            adf_lon: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    Direct,
+    Teardrop,
+    Parallel,
+}
+
+impl EntryKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Direct => "DIRECT",
+            EntryKind::Teardrop => "TEARDROP",
+            EntryKind::Parallel => "PARALLEL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EntryType {
+    Direct,
+    Teardrop,
+    Parallel,
+    Ambiguous {
+        first: EntryKind,
+        second: EntryKind,
+        boundary_offset: f64,
+    },
+}
+
+impl EntryType {
+    pub fn allows(&self, kind: EntryKind) -> bool {
+        match *self {
+            EntryType::Ambiguous { first, second, .. } => first == kind || second == kind,
+            single => single == EntryType::from(kind),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            EntryType::Direct => EntryKind::Direct.label().to_string(),
+            EntryType::Teardrop => EntryKind::Teardrop.label().to_string(),
+            EntryType::Parallel => EntryKind::Parallel.label().to_string(),
+            EntryType::Ambiguous { first, second, .. } => format!("{}/{}", first.label(), second.label()),
+        }
+    }
+}
+
+impl From<EntryKind> for EntryType {
+    fn from(kind: EntryKind) -> Self {
+        match kind {
+            EntryKind::Direct => EntryType::Direct,
+            EntryKind::Teardrop => EntryType::Teardrop,
+            EntryKind::Parallel => EntryType::Parallel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryRules {
+    Faa,
+    Icao,
+    // Jeppesen's manuals teach the ICAO sectors and their 5° flexibility, so this
+    // grades like Icao; it is listed for students who train from Jeppesen material.
+    Jeppesen,
+    Custom { tolerance: f64 },
+}
+
+impl EntryRules {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryRules::Faa => "FAA AIM",
+            EntryRules::Icao => "ICAO PANS-OPS / EASA",
+            EntryRules::Jeppesen => "Jeppesen (ICAO sectors)",
+            EntryRules::Custom { .. } => "Custom",
+        }
+    }
+
+    // Either entry is acceptable within this many degrees of a sector boundary.
+    pub fn boundary_tolerance(&self) -> f64 {
+        match self {
+            EntryRules::Faa => 0.0,
+            EntryRules::Icao | EntryRules::Jeppesen => 5.0,
+            EntryRules::Custom { tolerance } => *tolerance,
         }
     }
 }
@@ -34,43 +177,309 @@ pub struct TrackPoint {
     pub lat: f64,
     pub lon: f64,
     pub time: f64,
+    pub alt: f64,
+    pub heading: f64,
+    pub groundspeed: f64,
+    // Reported roll; None when the source only gives heading and position.
+    pub bank: Option<f64>,
+    // Reported IAS; None for sources and recordings that don't carry it.
+    pub indicated_airspeed: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Wind {
+    pub direction: f64,
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindLayer {
+    pub altitude: f64,
+    pub wind: Wind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WindModel {
+    pub layers: Vec<WindLayer>,
+}
+
+impl WindModel {
+    pub fn uniform(wind: Wind) -> Self {
+        Self {
+            layers: vec![WindLayer { altitude: 0.0, wind }],
+        }
+    }
+
+    // Layers are interpolated as vectors so a veering wind doesn't jump at 360/0.
+    pub fn wind_at(&self, altitude: f64) -> Wind {
+        let mut layers = self.layers.clone();
+        layers.sort_by(|a, b| a.altitude.total_cmp(&b.altitude));
+
+        let (first, last) = match (layers.first(), layers.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Wind::default(),
+        };
+        if altitude <= first.altitude {
+            return first.wind;
+        }
+        if altitude >= last.altitude {
+            return last.wind;
+        }
+
+        for pair in layers.windows(2) {
+            let (lower, upper) = (pair[0], pair[1]);
+            if altitude >= lower.altitude && altitude <= upper.altitude {
+                let span = upper.altitude - lower.altitude;
+                let t = if span > 0.0 { (altitude - lower.altitude) / span } else { 0.0 };
+
+                let to_vector = |w: Wind| {
+                    let rad = w.direction.to_radians();
+                    (w.speed * rad.sin(), w.speed * rad.cos())
+                };
+                let (x1, y1) = to_vector(lower.wind);
+                let (x2, y2) = to_vector(upper.wind);
+                let x = x1 + (x2 - x1) * t;
+                let y = y1 + (y2 - y1) * t;
+
+                return Wind {
+                    direction: (x.atan2(y).to_degrees() + 360.0) % 360.0,
+                    speed: (x * x + y * y).sqrt(),
+                };
+            }
+        }
+        last.wind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegLength {
+    Time(f64),
+    Distance(f64),
+}
+
+impl LegLength {
+    pub fn for_altitude(altitude: f64) -> Self {
+        if altitude > 14000.0 { LegLength::Time(90.0) } else { LegLength::Time(60.0) }
+    }
+
+    pub fn for_dme_hold(altitude: f64) -> Self {
+        if altitude > 14000.0 { LegLength::Distance(5.0) } else { LegLength::Distance(4.0) }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            LegLength::Time(seconds) => format!("{:.1} MIN", seconds / 60.0),
+            LegLength::Distance(nm) => format!("{:.0} NM", nm),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HoldingSide {
+    AwayFromStation,
+    TowardStation,
+}
+
+impl HoldingSide {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HoldingSide::AwayFromStation => "AWAY FROM",
+            HoldingSide::TowardStation => "TOWARD",
+        }
+    }
+}
+
+// The outbound leg of a DME hold ends where the aircraft reaches `distance` from the station.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DmeLimit {
+    pub station_lat: f64,
+    pub station_lon: f64,
+    pub distance: f64,
+    pub side: HoldingSide,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FixStation {
+    pub id: String,
+    pub lat: f64,
+    pub lon: f64,
+    // The station's radials are referenced to this (east positive).
+    pub declination: f64,
+    pub kind: NavaidKind,
+}
+
+impl FixStation {
+    pub fn new(id: String, lat: f64, lon: f64, kind: NavaidKind) -> Self {
+        Self {
+            id,
+            lat,
+            lon,
+            declination: magnetic::declination_now(lat, lon),
+            kind,
+        }
+    }
+
+    pub fn from_vor(vor: &VorInfo) -> Self {
+        Self {
+            id: vor.id.clone(),
+            lat: vor.lat,
+            lon: vor.lon,
+            declination: vor.declination,
+            kind: vor.kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoldingFix {
+    Station(FixStation),
+    RadialDme {
+        station: FixStation,
+        radial: f64,
+        distance: f64,
+    },
+    Intersection {
+        first: FixStation,
+        first_radial: f64,
+        second: FixStation,
+        second_radial: f64,
+    },
+    Waypoint {
+        name: String,
+        lat: f64,
+        lon: f64,
+    },
+}
+
+impl Default for HoldingFix {
+    fn default() -> Self {
+        HoldingFix::Station(FixStation::default())
+    }
+}
+
+impl HoldingFix {
+    pub fn name(&self) -> String {
+        match self {
+            HoldingFix::Station(station) => station.id.clone(),
+            HoldingFix::RadialDme { station, radial, distance } => {
+                format!("{} R-{:03.0} {:.0} DME", station.id, radial, distance)
+            }
+            HoldingFix::Intersection { first, first_radial, second, second_radial } => {
+                format!("{} R-{:03.0}/{} R-{:03.0}", first.id, first_radial, second.id, second_radial)
+            }
+            HoldingFix::Waypoint { name, .. } => name.clone(),
+        }
+    }
+
+    // Courses at a navaid fix follow the (first) station; a waypoint uses the local variation.
+    pub fn declination(&self) -> f64 {
+        match self {
+            HoldingFix::Station(station) | HoldingFix::RadialDme { station, .. } => station.declination,
+            HoldingFix::Intersection { first, .. } => first.declination,
+            HoldingFix::Waypoint { lat, lon, .. } => magnetic::declination_now(*lat, *lon),
+        }
+    }
+
+    pub fn stations(&self) -> Vec<&FixStation> {
+        match self {
+            HoldingFix::Station(station) | HoldingFix::RadialDme { station, .. } => vec![station],
+            HoldingFix::Intersection { first, second, .. } => vec![first, second],
+            HoldingFix::Waypoint { .. } => Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct HoldingPattern {
     pub active: bool,
+    pub fix: HoldingFix,
+    pub fix_lat: f64,
+    pub fix_lon: f64,
+    // Magnetic, as published; the courses below are true.
     pub radial: i32,
+    pub declination: f64,
     pub right_turns: bool,
     pub entry_captured: bool,
     pub start_heading: f64,
+    pub start_track: f64,
+    // ADF needle at capture: bearing to the fix relative to the nose.
+    pub start_relative_bearing: f64,
     pub entry_lat: f64,
     pub entry_lon: f64,
-    pub correct_entry: String,
+    pub correct_entry: Option<EntryType>,
     pub inbound_course: f64,
     pub outbound_course: f64,
     pub track_points: Vec<TrackPoint>,
     pub last_distance: f64,
+    pub wind: WindModel,
+    pub true_airspeed: f64,
+    pub bank_angle: f64,
+    pub leg: LegLength,
 }
 
 impl Default for HoldingPattern {
     fn default() -> Self {
         Self {
             active: false,
+            fix: HoldingFix::default(),
+            fix_lat: 0.0,
+            fix_lon: 0.0,
             radial: 0,
+            declination: 0.0,
             right_turns: true,
             entry_captured: false,
             start_heading: 0.0,
+            start_track: 0.0,
+            start_relative_bearing: 0.0,
             entry_lat: 0.0,
             entry_lon: 0.0,
-            correct_entry: String::new(),
+            correct_entry: None,
             inbound_course: 0.0,
             outbound_course: 0.0,
             track_points: Vec::new(),
             last_distance: 999.0,
+            wind: WindModel::default(),
+            true_airspeed: 180.0,
+            bank_angle: 25.0,
+            leg: LegLength::Time(60.0),
         }
     }
 }
 
+impl HoldingPattern {
+    // Held over an NDB: the course is a bearing to the station, not a radial.
+    pub fn is_ndb_hold(&self) -> bool {
+        matches!(&self.fix, HoldingFix::Station(station) if station.kind == NavaidKind::Ndb)
+    }
+
+    // What the entry is judged from. Over an NDB that's the bearing to the station
+    // read off the ADF (heading + relative bearing); elsewhere the track flown in.
+    pub fn entry_reference(&self) -> f64 {
+        if self.is_ndb_hold() {
+            normalize_angle(self.start_heading + self.start_relative_bearing)
+        } else {
+            self.start_track
+        }
+    }
+
+    pub fn set_radial(&mut self, radial: i32, declination: f64) {
+        self.radial = radial;
+        self.declination = declination;
+        self.outbound_course = magnetic_to_true(radial as f64, declination);
+        self.inbound_course = normalize_angle(self.outbound_course + 180.0);
+    }
+
+    // The hold as it stood at `time`: the track recorded up to that moment.
+    pub fn truncated_at(&self, time: f64) -> HoldingPattern {
+        let mut holding = self.clone();
+        holding.track_points.retain(|point| point.time <= time);
+        holding
+    }
+}
+
+// Two navaids with the same ident this close together are the same station.
+pub const SAME_STATION_NM: f64 = 10.0;
+
 #[derive(Debug, Clone)]
 pub struct VorInfo {
     pub country: String,
@@ -78,13 +487,73 @@ pub struct VorInfo {
     pub name: String,
     pub lat: f64,
     pub lon: f64,
+    // VHF navaids in 10 kHz units (11645 = 116.45 MHz), NDBs in kHz.
     pub freq: i32,
+    // Slaved variation the station's radials are aligned to (east positive).
+    pub declination: f64,
+    pub kind: NavaidKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NavaidKind {
+    #[default]
+    Vor,
+    VorDme,
+    Vortac,
+    Ndb,
+    Dme,
+}
+
+impl VorInfo {
+    pub fn frequency_label(&self) -> String {
+        self.kind.frequency_label(self.freq)
+    }
+
+    // Whether this is the station `id` near lat/lon. Sources disagree on the country
+    // ("Spain" or the region code "LE") and on the exact position (airport reference
+    // or antenna), so the ident, NDB or not, and distance are what tell stations apart.
+    pub fn is_station(&self, id: &str, lat: f64, lon: f64, ndb: bool) -> bool {
+        self.id == id && (self.kind == NavaidKind::Ndb) == ndb && calculate_distance(self.lat, self.lon, lat, lon) <= SAME_STATION_NM
+    }
+}
+
+impl NavaidKind {
+    pub fn has_radials(&self) -> bool {
+        matches!(self, NavaidKind::Vor | NavaidKind::VorDme | NavaidKind::Vortac)
+    }
+
+    // NDBs are tuned in kHz, everything else in 10 kHz units shown as MHz.
+    pub fn frequency_label(&self, freq: i32) -> String {
+        match self {
+            NavaidKind::Ndb => format!("{} kHz", freq),
+            _ => format!("{:.2} MHz", freq as f64 / 100.0),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NavaidKind::Vor => "VOR",
+            NavaidKind::VorDme => "VOR-DME",
+            NavaidKind::Vortac => "VORTAC",
+            NavaidKind::Ndb => "NDB",
+            NavaidKind::Dme => "DME",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Tab {
     Simulate,
-    XPlane11,
+    Live,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceKind {
+    FileBridge(PathBuf),
+    XPlaneUdp(SocketAddr),
+    FlightGear(u16),
+    FlightGearTelnet(SocketAddr),
+    Replay(PathBuf),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -0,0 +1,200 @@
+use crate::calculations::{angle_difference, destination_point, ground_track, normalize_angle, true_to_magnetic};
+use crate::data::{TrackPoint, Wind, XPlaneData};
+use crate::magnetic;
+
+// 9.80665 m/s² expressed in knots per second.
+const GRAVITY_KT_PER_S: f64 = 19.0626;
+const STANDARD_RATE_DEG_S: f64 = 3.0;
+const ROLL_RATE_DEG_S: f64 = 10.0;
+const ACCELERATION_KT_S: f64 = 2.0;
+// Within this many degrees of the bug the autopilot stops forcing a turn direction.
+const BUG_CAPTURE_DEG: f64 = 5.0;
+const MAX_STEP_S: f64 = 0.1;
+// Bank commanded per degree of heading error, so the autopilot rolls out onto the bug.
+const HEADING_BUG_GAIN: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnDirection {
+    Shortest,
+    Left,
+    Right,
+}
+
+// Point-mass aircraft: flies at constant TAS and altitude, turns at standard rate
+// (limited by `max_bank`) towards the autopilot heading bug and drifts with the wind.
+#[derive(Debug, Clone)]
+pub struct FlightModel {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: f64,
+    pub heading: f64,
+    pub bank: f64,
+    pub true_airspeed: f64,
+    pub target_airspeed: f64,
+    pub max_bank: f64,
+    pub heading_bug: f64,
+    pub turn_direction: TurnDirection,
+    pub groundspeed: f64,
+    pub track: f64,
+}
+
+impl FlightModel {
+    pub fn new(lat: f64, lon: f64, altitude: f64, heading: f64, true_airspeed: f64) -> Self {
+        Self {
+            lat,
+            lon,
+            altitude,
+            heading,
+            bank: 0.0,
+            true_airspeed,
+            target_airspeed: true_airspeed,
+            max_bank: 25.0,
+            heading_bug: heading,
+            turn_direction: TurnDirection::Shortest,
+            groundspeed: true_airspeed,
+            track: heading,
+        }
+    }
+
+    // Moves the bug and keeps turning the requested way, even past 180°.
+    pub fn turn_bug(&mut self, delta: f64) {
+        self.heading_bug = normalize_angle(self.heading_bug + delta);
+        self.turn_direction = if delta < 0.0 { TurnDirection::Left } else { TurnDirection::Right };
+    }
+
+    // Picks the flight up again at a recorded point, keeping the speed and bank
+    // limit set for the hold. Bank and the bug aren't recorded: it resumes wings level.
+    pub fn resume_from(&self, point: &TrackPoint) -> Self {
+        Self {
+            target_airspeed: self.target_airspeed,
+            max_bank: self.max_bank,
+            groundspeed: point.groundspeed,
+            ..Self::new(point.lat, point.lon, point.alt, point.heading, self.true_airspeed)
+        }
+    }
+
+    pub fn set_heading_bug(&mut self, heading: f64) {
+        self.heading_bug = normalize_angle(heading);
+        self.turn_direction = TurnDirection::Shortest;
+    }
+
+    pub fn turn_bank(&self) -> f64 {
+        let rate = STANDARD_RATE_DEG_S.to_radians();
+        let standard_rate_bank = (rate * self.true_airspeed / GRAVITY_KT_PER_S).atan().to_degrees();
+        standard_rate_bank.min(self.max_bank)
+    }
+
+    pub fn step(&mut self, dt: f64, wind: Wind) {
+        let mut remaining = dt;
+        while remaining > 0.0 {
+            let h = remaining.min(MAX_STEP_S);
+            self.integrate(h, wind);
+            remaining -= h;
+        }
+    }
+
+    fn integrate(&mut self, dt: f64, wind: Wind) {
+        let max_change = ACCELERATION_KT_S * dt;
+        self.true_airspeed += (self.target_airspeed - self.true_airspeed).clamp(-max_change, max_change);
+
+        let limit = self.turn_bank();
+        let mut error = angle_difference(self.heading_bug, self.heading);
+        if error.abs() <= BUG_CAPTURE_DEG {
+            self.turn_direction = TurnDirection::Shortest;
+        }
+        match self.turn_direction {
+            TurnDirection::Left if error > 0.0 => error -= 360.0,
+            TurnDirection::Right if error < 0.0 => error += 360.0,
+            _ => {}
+        }
+        let target_bank = (error * HEADING_BUG_GAIN).clamp(-limit, limit);
+        let max_roll = ROLL_RATE_DEG_S * dt;
+        self.bank += (target_bank - self.bank).clamp(-max_roll, max_roll);
+
+        if self.true_airspeed > 0.0 {
+            let turn_rate = (GRAVITY_KT_PER_S * self.bank.to_radians().tan() / self.true_airspeed).to_degrees();
+            self.heading = normalize_angle(self.heading + turn_rate * dt);
+        }
+
+        let (track, groundspeed) = ground_track(self.heading, self.true_airspeed, wind);
+        let (lat, lon) = destination_point(self.lat, self.lon, track, groundspeed * dt / 3600.0);
+        self.lat = lat;
+        self.lon = lon;
+        self.groundspeed = groundspeed;
+        self.track = track;
+    }
+
+    // Rule of thumb: TAS is 2% higher than IAS per 1000 ft.
+    pub fn indicated_airspeed(&self) -> f64 {
+        self.true_airspeed / (1.0 + 0.02 * self.altitude / 1000.0)
+    }
+
+    // The model flies true headings; the magnetic one is what the cockpit would show.
+    pub fn write_to(&self, data: &mut XPlaneData, wind: Wind) {
+        data.aircraft_lat = self.lat;
+        data.aircraft_lon = self.lon;
+        data.aircraft_alt = self.altitude;
+        data.magnetic_variation = magnetic::declination_now(self.lat, self.lon);
+        data.aircraft_heading = true_to_magnetic(self.heading, data.magnetic_variation);
+        data.aircraft_groundspeed = self.groundspeed;
+        data.aircraft_heading_true = self.heading;
+        data.aircraft_track = self.track;
+        data.indicated_airspeed = self.indicated_airspeed();
+        data.bank_angle = self.bank;
+        data.vertical_speed = 0.0;
+        data.wind_direction = wind.direction;
+        data.wind_speed = wind.speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculations::{calculate_bearing, calculate_distance};
+
+    #[test]
+    fn turns_at_standard_rate_within_the_bank_limit() {
+        let mut model = FlightModel::new(40.0, -3.0, 5000.0, 0.0, 120.0);
+        model.set_heading_bug(180.0);
+        // Rolled in after two seconds, then three degrees a second.
+        model.step(3.0, Wind::default());
+        assert!((model.bank - model.turn_bank()).abs() < 1e-9 && model.bank > 0.0);
+        let heading = model.heading;
+        model.step(10.0, Wind::default());
+        assert!((angle_difference(model.heading, heading) - 30.0).abs() < 0.01);
+
+        // Too fast for standard rate at 20° of bank: the limit wins and the turn is slower.
+        let mut fast = FlightModel::new(40.0, -3.0, 5000.0, 0.0, 200.0);
+        fast.max_bank = 20.0;
+        assert_eq!(fast.turn_bank(), 20.0);
+        fast.set_heading_bug(180.0);
+        fast.step(13.0, Wind::default());
+        assert!(fast.heading < 30.0);
+
+        // Asking for 270° to the right turns right, the long way round.
+        let mut model = FlightModel::new(40.0, -3.0, 5000.0, 0.0, 120.0);
+        model.turn_bug(270.0);
+        model.step(5.0, Wind::default());
+        assert!(model.heading > 0.0 && model.heading < 90.0);
+    }
+
+    #[test]
+    fn drifts_with_the_wind() {
+        let mut model = FlightModel::new(40.0, -3.0, 5000.0, 0.0, 120.0);
+        let wind = Wind { direction: 270.0, speed: 20.0 };
+        model.step(60.0, wind);
+
+        // Heading north for a minute with 20 kt from the west: 2 NM north, 1/3 NM east.
+        assert_eq!(model.heading, 0.0);
+        let expected_track = (20.0f64 / 120.0).atan().to_degrees();
+        assert!((model.track - expected_track).abs() < 1e-6);
+        assert!((model.groundspeed - (120.0f64.powi(2) + 20.0f64.powi(2)).sqrt()).abs() < 1e-6);
+        assert!((calculate_bearing(40.0, -3.0, model.lat, model.lon) - expected_track).abs() < 0.05);
+        assert!((calculate_distance(40.0, -3.0, model.lat, model.lon) - model.groundspeed / 60.0).abs() < 0.001);
+
+        let mut data = XPlaneData::default();
+        model.write_to(&mut data, wind);
+        assert_eq!((data.aircraft_heading_true, data.aircraft_track), (model.heading, model.track));
+        assert_eq!((data.wind_direction, data.wind_speed), (270.0, 20.0));
+    }
+}
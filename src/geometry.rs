@@ -0,0 +1,258 @@
+use crate::calculations::{calculate_distance, dme_limit, normalize_angle};
+use crate::data::{DmeLimit, HoldingPattern, LegLength};
+
+const EARTH_RADIUS_NM: f64 = 3440.065;
+const ARC_SEGMENTS: usize = 36;
+
+pub fn turn_radius_nm(true_airspeed: f64, bank_angle: f64) -> f64 {
+    let standard_rate_radius = true_airspeed / (60.0 * std::f64::consts::PI);
+    let bank = bank_angle.clamp(1.0, 60.0).to_radians();
+    // r = V² / (g · tan φ) with V in kt, g in kt/h → 68 625 kt²/NM
+    let bank_radius = true_airspeed * true_airspeed / (68625.0 * bank.tan());
+    bank_radius.max(standard_rate_radius)
+}
+
+// Flat-earth east/north offsets in NM around a reference point; fine at holding scale.
+pub fn to_local_nm(lat: f64, lon: f64, ref_lat: f64, ref_lon: f64) -> (f64, f64) {
+    let east = (lon - ref_lon).to_radians() * ref_lat.to_radians().cos() * EARTH_RADIUS_NM;
+    let north = (lat - ref_lat).to_radians() * EARTH_RADIUS_NM;
+    (east, north)
+}
+
+pub fn from_local_nm(east: f64, north: f64, ref_lat: f64, ref_lon: f64) -> (f64, f64) {
+    let lat = ref_lat + (north / EARTH_RADIUS_NM).to_degrees();
+    let lon = ref_lon + (east / (EARTH_RADIUS_NM * ref_lat.to_radians().cos())).to_degrees();
+    (lat, lon)
+}
+
+// Position relative to the hold: `along` is measured in the inbound direction
+// (negative before the fix), `cross` is positive on the holding side.
+pub fn hold_frame(lat: f64, lon: f64, fix_lat: f64, fix_lon: f64, inbound_course: f64, right_turns: bool) -> (f64, f64) {
+    let (east, north) = to_local_nm(lat, lon, fix_lat, fix_lon);
+    let course = inbound_course.to_radians();
+    let side = if right_turns { 1.0 } else { -1.0 };
+    let along = east * course.sin() + north * course.cos();
+    let cross = (east * course.cos() - north * course.sin()) * side;
+    (along, cross)
+}
+
+pub fn from_hold_frame(along: f64, cross: f64, fix_lat: f64, fix_lon: f64, inbound_course: f64, right_turns: bool) -> (f64, f64) {
+    let course = inbound_course.to_radians();
+    let cross = if right_turns { cross } else { -cross };
+    let east = along * course.sin() + cross * course.cos();
+    let north = along * course.cos() - cross * course.sin();
+    from_local_nm(east, north, fix_lat, fix_lon)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RacetrackParams {
+    pub fix_lat: f64,
+    pub fix_lon: f64,
+    pub inbound_course: f64,
+    pub right_turns: bool,
+    pub true_airspeed: f64,
+    pub bank_angle: f64,
+    pub leg: LegLength,
+    pub dme_limit: Option<DmeLimit>,
+}
+
+impl RacetrackParams {
+    pub fn from_holding(holding: &HoldingPattern) -> Self {
+        Self {
+            fix_lat: holding.fix_lat,
+            fix_lon: holding.fix_lon,
+            inbound_course: holding.inbound_course,
+            right_turns: holding.right_turns,
+            true_airspeed: holding.true_airspeed,
+            bank_angle: holding.bank_angle,
+            leg: holding.leg,
+            dme_limit: dme_limit(&holding.fix, holding.outbound_course, holding.leg),
+        }
+    }
+
+    pub fn leg_length_nm(&self) -> f64 {
+        let nominal = match self.leg {
+            LegLength::Time(seconds) => self.true_airspeed * seconds / 3600.0,
+            LegLength::Distance(nm) => nm,
+        };
+        self.dme_limit
+            .and_then(|limit| self.dme_leg_length_nm(limit))
+            .unwrap_or(nominal)
+    }
+
+    // The outbound leg runs parallel to the inbound course two turn radii out; it
+    // ends at the first point along it where the slant range to the station equals
+    // the limit.
+    fn dme_leg_length_nm(&self, limit: DmeLimit) -> Option<f64> {
+        let offset = 2.0 * turn_radius_nm(self.true_airspeed, self.bank_angle);
+        let (station_along, station_cross) = hold_frame(
+            limit.station_lat,
+            limit.station_lon,
+            self.fix_lat,
+            self.fix_lon,
+            self.inbound_course,
+            self.right_turns,
+        );
+
+        let lateral = offset - station_cross;
+        let reach_sq = limit.distance * limit.distance - lateral * lateral;
+        if reach_sq < 0.0 {
+            return None;
+        }
+        let reach = reach_sq.sqrt();
+
+        [station_along + reach, station_along - reach]
+            .into_iter()
+            .filter(|&along| along < 0.0)
+            .map(|along| -along)
+            .reduce(f64::min)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Racetrack {
+    pub points: Vec<(f64, f64)>,
+    pub turn_radius: f64,
+    pub leg_length: f64,
+}
+
+// Nominal no-wind racetrack starting and ending at the fix: turn to outbound,
+// outbound leg, turn to inbound, inbound leg back to the fix.
+pub fn build_racetrack(params: &RacetrackParams) -> Racetrack {
+    let radius = turn_radius_nm(params.true_airspeed, params.bank_angle);
+    let leg_length = params.leg_length_nm();
+
+    let course = params.inbound_course.to_radians();
+    let side = if params.right_turns { 1.0 } else { -1.0 };
+    let (ux, uy) = (course.sin(), course.cos());
+    let (nx, ny) = (course.cos() * side, -course.sin() * side);
+
+    let mut local: Vec<(f64, f64)> = Vec::new();
+
+    let first_center = (nx * radius, ny * radius);
+    append_arc(&mut local, first_center, radius, params.inbound_course - 90.0 * side, side);
+
+    let second_center = (first_center.0 - ux * leg_length, first_center.1 - uy * leg_length);
+    append_arc(&mut local, second_center, radius, params.inbound_course + 90.0 * side, side);
+
+    local.push((0.0, 0.0));
+
+    let points = local
+        .iter()
+        .map(|&(east, north)| from_local_nm(east, north, params.fix_lat, params.fix_lon))
+        .collect();
+
+    Racetrack {
+        points,
+        turn_radius: radius,
+        leg_length,
+    }
+}
+
+fn append_arc(points: &mut Vec<(f64, f64)>, center: (f64, f64), radius: f64, start_bearing: f64, side: f64) {
+    for i in 0..=ARC_SEGMENTS {
+        let bearing = normalize_angle(start_bearing + side * 180.0 * i as f64 / ARC_SEGMENTS as f64).to_radians();
+        points.push((center.0 + radius * bearing.sin(), center.1 + radius * bearing.cos()));
+    }
+}
+
+impl Racetrack {
+    pub fn distance_to(&self, lat: f64, lon: f64) -> f64 {
+        let (ref_lat, ref_lon) = match self.points.first() {
+            Some(&p) => p,
+            None => return f64::INFINITY,
+        };
+        if self.points.len() == 1 {
+            return calculate_distance(lat, lon, ref_lat, ref_lon);
+        }
+
+        let (px, py) = to_local_nm(lat, lon, ref_lat, ref_lon);
+        self.points
+            .windows(2)
+            .map(|pair| {
+                let (ax, ay) = to_local_nm(pair[0].0, pair[0].1, ref_lat, ref_lon);
+                let (bx, by) = to_local_nm(pair[1].0, pair[1].1, ref_lat, ref_lon);
+                distance_to_segment((px, py), (ax, ay), (bx, by))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+pub fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - cx).powi(2) + (p.1 - cy).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(right_turns: bool, true_airspeed: f64) -> RacetrackParams {
+        RacetrackParams {
+            fix_lat: 40.0,
+            fix_lon: -3.0,
+            inbound_course: 90.0,
+            right_turns,
+            true_airspeed,
+            bank_angle: 25.0,
+            leg: LegLength::Time(60.0),
+            dme_limit: None,
+        }
+    }
+
+    #[test]
+    fn turns_at_standard_rate_unless_the_bank_limit_is_reached() {
+        // 100 kt at standard rate needs about 15° of bank: well inside the limit.
+        assert!((turn_radius_nm(100.0, 25.0) - 100.0 / (60.0 * std::f64::consts::PI)).abs() < 1e-9);
+        // 180 kt would need 27°, so 25° of bank sets a wider radius.
+        let limited = turn_radius_nm(180.0, 25.0);
+        assert!((limited - 180.0 * 180.0 / (68625.0 * 25f64.to_radians().tan())).abs() < 1e-9);
+        assert!(limited > 180.0 / (60.0 * std::f64::consts::PI));
+        assert!(turn_radius_nm(180.0, 30.0) < limited);
+    }
+
+    #[test]
+    fn closes_the_racetrack_through_the_fix() {
+        let params = params(true, 180.0);
+        let racetrack = build_racetrack(&params);
+        let frame = |&(lat, lon): &(f64, f64)| hold_frame(lat, lon, params.fix_lat, params.fix_lon, params.inbound_course, true);
+        let close = |(along, cross): (f64, f64), expected: (f64, f64)| (along - expected.0).abs() < 0.01 && (cross - expected.1).abs() < 0.01;
+        let (radius, leg) = (racetrack.turn_radius, racetrack.leg_length);
+        assert!((leg - 3.0).abs() < 1e-9);
+
+        // Fix, end of the turn outbound, start and end of the turn inbound, fix.
+        let points = &racetrack.points;
+        assert!(close(frame(&points[0]), (0.0, 0.0)));
+        assert!(close(frame(&points[ARC_SEGMENTS]), (0.0, 2.0 * radius)));
+        assert!(close(frame(&points[ARC_SEGMENTS + 1]), (-leg, 2.0 * radius)));
+        assert!(close(frame(&points[2 * ARC_SEGMENTS + 1]), (-leg, 0.0)));
+        assert!(close(frame(points.last().unwrap()), (0.0, 0.0)));
+        assert_eq!(points.len(), 2 * ARC_SEGMENTS + 3);
+
+        // The nominal track is on the racetrack everywhere, and never on the far side.
+        assert!(points.iter().all(|point| racetrack.distance_to(point.0, point.1) < 1e-6));
+        assert!(points.iter().all(|point| frame(point).1 > -0.01));
+    }
+
+    #[test]
+    fn left_turns_mirror_right_turns_across_the_inbound_course() {
+        let right = build_racetrack(&params(true, 180.0));
+        let left = build_racetrack(&params(false, 180.0));
+        assert_eq!(right.points.len(), left.points.len());
+
+        for (r, l) in right.points.iter().zip(&left.points) {
+            let (r_east, r_north) = to_local_nm(r.0, r.1, 40.0, -3.0);
+            let (l_east, l_north) = to_local_nm(l.0, l.1, 40.0, -3.0);
+            // Inbound 090°: right turns hold to the south, left turns to the north.
+            assert!(r_north < 0.01 && l_north > -0.01);
+            assert!((r_east - l_east).abs() < 1e-6 && (r_north + l_north).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::calculations::magnetic_to_true;
+use crate::data::XPlaneData;
+
+// World Magnetic Model 2025 (epoch 2025.0), truncated to degree and order 6. The
+// low degrees carry the main field, so declination stays within a degree or two of
+// the full model away from the magnetic poles, which is plenty for holding practice.
+// Columns: n, m, g, h (nT), secular variation of g and h (nT/year).
+const WMM_EPOCH: f64 = 2025.0;
+// The secular variation is only published for five years; past that the model is
+// held at its last valid date rather than extrapolated.
+const WMM_VALID_UNTIL: f64 = 2030.0;
+const WMM_DEGREE: usize = 6;
+const WMM_COEFFICIENTS: [(usize, usize, f64, f64, f64, f64); 27] = [
+    (1, 0, -29351.8, 0.0, 12.0, 0.0),
+    (1, 1, -1410.8, 4545.4, 9.7, -21.5),
+    (2, 0, -2556.6, 0.0, -11.6, 0.0),
+    (2, 1, 2951.1, -3133.6, -5.2, -27.7),
+    (2, 2, 1649.3, -815.1, -8.0, -12.1),
+    (3, 0, 1361.0, 0.0, -1.3, 0.0),
+    (3, 1, -2404.1, -56.6, -4.2, 4.0),
+    (3, 2, 1243.8, 237.5, 0.4, -0.3),
+    (3, 3, 453.6, -549.5, -15.6, -4.1),
+    (4, 0, 895.0, 0.0, -1.6, 0.0),
+    (4, 1, 799.5, 278.6, -2.4, -1.1),
+    (4, 2, 55.7, -133.9, -6.0, 4.1),
+    (4, 3, -281.1, 212.0, 5.6, 1.6),
+    (4, 4, 12.1, -375.6, -7.0, -4.4),
+    (5, 0, -233.2, 0.0, 0.6, 0.0),
+    (5, 1, 368.9, 45.4, 1.4, -0.5),
+    (5, 2, 187.2, 220.2, 0.0, 2.2),
+    (5, 3, -138.7, -122.9, 0.6, 0.4),
+    (5, 4, -142.0, 43.0, 2.2, 1.7),
+    (5, 5, 20.9, 106.1, 0.9, 1.9),
+    (6, 0, 64.4, 0.0, -0.2, 0.0),
+    (6, 1, 63.8, -18.4, -0.4, 0.3),
+    (6, 2, 76.9, 16.8, 0.9, -1.6),
+    (6, 3, -115.7, 48.8, 1.2, -0.4),
+    (6, 4, -40.9, -59.8, -0.9, 0.9),
+    (6, 5, 14.9, 10.9, 0.3, 0.7),
+    (6, 6, -60.7, 72.7, 0.9, 0.9),
+];
+
+const REFERENCE_RADIUS_KM: f64 = 6371.2;
+// WGS84 semi-axes.
+const EQUATORIAL_RADIUS_KM: f64 = 6378.137;
+const POLAR_RADIUS_KM: f64 = 6356.7523142;
+
+// Magnetic declination in degrees, east positive (magnetic = true - declination).
+// Dates outside the model's validity are evaluated at the nearest valid one.
+pub fn declination(lat: f64, lon: f64, altitude_ft: f64, year: f64) -> f64 {
+    let year = year.clamp(WMM_EPOCH, WMM_VALID_UNTIL);
+    let (north, east) = horizontal_field(lat, lon, altitude_ft * 0.0003048, year);
+    east.atan2(north).to_degrees()
+}
+
+// Sea-level declination for today, evaluated offline from the embedded model.
+pub fn declination_now(lat: f64, lon: f64) -> f64 {
+    declination(lat, lon, 0.0, current_year())
+}
+
+// Older bridge scripts, the six-chunk FlightGear protocol and early recordings only
+// carry the magnetic heading; capture and the map need the true one.
+pub fn fill_missing_variation(data: &mut XPlaneData) {
+    if data.magnetic_variation == 0.0 && data.aircraft_heading_true == 0.0 {
+        data.magnetic_variation = declination_now(data.aircraft_lat, data.aircraft_lon);
+        data.aircraft_heading_true = magnetic_to_true(data.aircraft_heading, data.magnetic_variation);
+    }
+}
+
+// Published variation as "-1.5", "3E" or "12.5W"; east positive.
+pub fn parse_declination(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (number, sign) = match text.chars().last()? {
+        'E' | 'e' => (&text[..text.len() - 1], 1.0),
+        'W' | 'w' => (&text[..text.len() - 1], -1.0),
+        _ => (text, 1.0),
+    };
+    let value = number.trim().parse::<f64>().ok()?;
+    (value.abs() <= 180.0).then_some(sign * value)
+}
+
+// Set once the embedded model has expired, for the validation report.
+pub fn model_warning() -> Option<String> {
+    (current_year() > WMM_VALID_UNTIL).then(|| {
+        format!("magnetic model WMM2025 expired in {:.0}; variation is computed for {:.1}", WMM_VALID_UNTIL, WMM_VALID_UNTIL)
+    })
+}
+
+fn current_year() -> f64 {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    1970.0 + seconds / (365.2425 * 86400.0)
+}
+
+// North and east field components (nT) at a geodetic position, following the
+// spherical harmonic synthesis in the WMM technical report.
+fn horizontal_field(lat: f64, lon: f64, altitude_km: f64, year: f64) -> (f64, f64) {
+    let dt = year - WMM_EPOCH;
+    let lat_rad = lat.clamp(-89.999, 89.999).to_radians();
+    let lon_rad = lon.to_radians();
+
+    // Geodetic to geocentric spherical coordinates.
+    let a2 = EQUATORIAL_RADIUS_KM * EQUATORIAL_RADIUS_KM;
+    let b2 = POLAR_RADIUS_KM * POLAR_RADIUS_KM;
+    let c2 = a2 - b2;
+    let (sin_lat, cos_lat) = lat_rad.sin_cos();
+    let q = (a2 - c2 * sin_lat * sin_lat).sqrt();
+    let q1 = altitude_km * q;
+    let q2 = ((q1 + a2) / (q1 + b2)).powi(2);
+    let cos_theta = sin_lat / (q2 * cos_lat * cos_lat + sin_lat * sin_lat).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    let r = (altitude_km * altitude_km + 2.0 * q1 + (a2 * a2 - c2 * (a2 + b2) * sin_lat * sin_lat) / (q * q)).sqrt();
+    let d = (a2 * cos_lat * cos_lat + b2 * sin_lat * sin_lat).sqrt();
+    let cos_rotation = (altitude_km + d) / r;
+    let sin_rotation = c2 * cos_lat * sin_lat / (r * d);
+
+    // Gauss-normalised associated Legendre functions and their theta derivatives.
+    let n_max = WMM_DEGREE;
+    let mut p = [[0.0f64; WMM_DEGREE + 1]; WMM_DEGREE + 1];
+    let mut dp = [[0.0f64; WMM_DEGREE + 1]; WMM_DEGREE + 1];
+    p[0][0] = 1.0;
+    for n in 1..=n_max {
+        for m in 0..=n {
+            if n == m {
+                p[n][m] = sin_theta * p[n - 1][m - 1];
+                dp[n][m] = sin_theta * dp[n - 1][m - 1] + cos_theta * p[n - 1][m - 1];
+            } else {
+                let k = if n > 1 {
+                    (((n - 1) * (n - 1)) as f64 - (m * m) as f64) / (((2 * n - 1) * (2 * n - 3)) as f64)
+                } else {
+                    0.0
+                };
+                let (p2, dp2) = if n > 1 { (p[n - 2][m], dp[n - 2][m]) } else { (0.0, 0.0) };
+                p[n][m] = cos_theta * p[n - 1][m] - k * p2;
+                dp[n][m] = cos_theta * dp[n - 1][m] - sin_theta * p[n - 1][m] - k * dp2;
+            }
+        }
+    }
+
+    // Schmidt semi-normalisation factors turn them into the WMM's convention.
+    let mut schmidt = [[0.0f64; WMM_DEGREE + 1]; WMM_DEGREE + 1];
+    schmidt[0][0] = 1.0;
+    for n in 1..=n_max {
+        schmidt[n][0] = schmidt[n - 1][0] * (2 * n - 1) as f64 / n as f64;
+        for m in 1..=n {
+            let factor = if m == 1 { 2.0 } else { 1.0 };
+            schmidt[n][m] = schmidt[n][m - 1] * (((n - m + 1) as f64 * factor) / (n + m) as f64).sqrt();
+        }
+    }
+
+    let (mut b_r, mut b_theta, mut b_phi) = (0.0, 0.0, 0.0);
+    for &(n, m, g, h, g_dot, h_dot) in &WMM_COEFFICIENTS {
+        let g = (g + g_dot * dt) * schmidt[n][m];
+        let h = (h + h_dot * dt) * schmidt[n][m];
+        let ratio = (REFERENCE_RADIUS_KM / r).powi(n as i32 + 2);
+        let (sin_m, cos_m) = (m as f64 * lon_rad).sin_cos();
+        let cosine_term = g * cos_m + h * sin_m;
+        let sine_term = g * sin_m - h * cos_m;
+
+        b_r += (n + 1) as f64 * ratio * cosine_term * p[n][m];
+        b_theta -= ratio * cosine_term * dp[n][m];
+        b_phi += m as f64 * ratio * sine_term * p[n][m];
+    }
+    b_phi /= sin_theta;
+
+    let north = -b_theta * cos_rotation - b_r * sin_rotation;
+    (north, b_phi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_published_declinations() {
+        // Values from the full WMM2025 at 2025.0, sea level. Truncation costs up to a
+        // degree and a half where the crustal field is strong (southern Africa).
+        let cases = [
+            (40.0, -105.25, 7.6),
+            (40.47, -3.56, 0.0),
+            (61.17, -150.0, 14.3),
+            (-33.95, 151.18, 12.9),
+            (-33.97, 18.6, -26.4),
+            (35.55, 139.78, -7.9),
+        ];
+        for (lat, lon, expected) in cases {
+            let computed = declination(lat, lon, 0.0, 2025.0);
+            assert!((computed - expected).abs() < 2.0, "{} {}: {:.1} vs {}", lat, lon, computed, expected);
+        }
+    }
+
+    #[test]
+    fn holds_the_model_at_its_validity_limits() {
+        let at = |year: f64| declination(40.47, -3.56, 0.0, year);
+        assert_eq!(at(2019.0), at(2025.0));
+        assert_eq!(at(2041.5), at(2030.0));
+        assert_ne!(at(2027.5), at(2025.0));
+    }
+
+    #[test]
+    fn parses_published_variation() {
+        assert_eq!(parse_declination("3E"), Some(3.0));
+        assert_eq!(parse_declination(" 12.5W"), Some(-12.5));
+        assert_eq!(parse_declination("-1.5"), Some(-1.5));
+        assert_eq!(parse_declination(""), None);
+        assert_eq!(parse_declination("E"), None);
+        assert_eq!(parse_declination("400"), None);
+    }
+}
@@ -1,24 +1,42 @@
 #![windows_subsystem = "windows"]
 
 use eframe::egui;
-use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+mod analysis;
+mod capture;
 mod data;
 mod calculations;
+mod flight_model;
+mod geometry;
+mod magnetic;
+mod navdata;
+mod navindex;
+mod scoring;
+mod sim_clock;
+mod sources;
+mod stations;
 mod tile_manager;
 mod ui;
-
-use data::{XPlaneData, HoldingPattern, VorInfo, Tab, DisplayMode};
-use calculations::{calculate_distance, calculate_bearing, calculate_entry_type};
+mod user_files;
+
+use data::{XPlaneData, HoldingPattern, VorInfo, NavaidKind, Tab, SourceKind, DisplayMode, EntryRules, FixStation, HoldingFix, LegLength};
+use calculations::{angle_difference, calculate_distance, calculate_bearing, calculate_entry_type, destination_point, ground_track, magnetic_to_true, relative_bearing, resolve_fix, true_to_magnetic};
+use flight_model::FlightModel;
+use sim_clock::SimClock;
+use navdata::ValidationReport;
+use navindex::NavaidIndex;
+use sources::{LinkHealth, LiveLink, Recorder, SourceSettings};
+use stations::{SavedStations, StationKey};
 use tile_manager::TileManager;
 
 struct HoldingViewerApp {
     xplane_data: Arc<Mutex<XPlaneData>>,
     holding: Arc<Mutex<HoldingPattern>>,
-    data_file_path: PathBuf,
+    live_link: Arc<Mutex<LiveLink>>,
+    source_settings: SourceSettings,
     tile_manager: TileManager,
     zoom: u8,
     show_overlay: bool,
@@ -28,38 +46,43 @@ struct HoldingViewerApp {
     active_tab: Tab,
     simulated_data: XPlaneData,
     simulated_holding: HoldingPattern,
+    flight_model: FlightModel,
+    sim_clock: SimClock,
+    scrub_time: Option<f64>,
     show_about: bool,
     show_how_it_works: bool,
-    available_vors: Vec<VorInfo>,
+    available_vors: Arc<NavaidIndex>,
+    // The same index, swapped in for the telemetry thread when another database is imported.
+    shared_vors: Arc<Mutex<Arc<NavaidIndex>>>,
+    navaid_path: String,
+    navaid_status: Option<String>,
+    navaid_report: ValidationReport,
     selected_vor_index: usize,
-    country_filter: String,
+    station_query: String,
+    saved_stations: SavedStations,
     display_mode: DisplayMode,
+    entry_rules: EntryRules,
 }
 
 impl HoldingViewerApp {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let temp_dir = std::env::var("TEMP")
-            .or_else(|_| std::env::var("TMP"))
-            .unwrap_or_else(|_| {
-                let appdata = std::env::var("LOCALAPPDATA")
-                    .unwrap_or_else(|_| std::env::var("USERPROFILE").unwrap_or_else(|_| String::from("C:\\")));
-                format!("{}\\Temp", appdata)
-            });
-        let data_file_path = PathBuf::from(temp_dir).join("xplane_data.json");
-
-        let available_vors = Self::load_vors();
-        let first_vor = &available_vors[0];
-
-        let mut sim_data = XPlaneData::default();
-        sim_data.vor_id = first_vor.id.clone();
-        sim_data.vor_freq = first_vor.freq;
-        sim_data.vor_lat = first_vor.lat;
-        sim_data.vor_lon = first_vor.lon;
+        let (available_vors, navaid_report) = navdata::load_navaid_database(&navdata::data_directory());
+        let available_vors = Arc::new(NavaidIndex::new(available_vors));
+        let first_vor = &available_vors.navaids()[0];
+
+        let sim_data = XPlaneData {
+            vor_id: first_vor.id.clone(),
+            vor_freq: first_vor.freq,
+            vor_lat: first_vor.lat,
+            vor_lon: first_vor.lon,
+            ..Default::default()
+        };
 
         let app = Self {
             xplane_data: Arc::new(Mutex::new(XPlaneData::default())),
             holding: Arc::new(Mutex::new(HoldingPattern::default())),
-            data_file_path,
+            live_link: Arc::new(Mutex::new(LiveLink::default())),
+            source_settings: SourceSettings::default(),
             tile_manager: TileManager::new(),
             zoom: 11,
             show_overlay: true,
@@ -69,102 +92,109 @@ impl HoldingViewerApp {
             active_tab: Tab::Simulate,
             simulated_data: sim_data,
             simulated_holding: HoldingPattern::default(),
+            flight_model: FlightModel::new(0.0, 0.0, 0.0, 0.0, 180.0),
+            sim_clock: SimClock::new(0.5),
+            scrub_time: None,
             show_about: false,
             show_how_it_works: false,
+            shared_vors: Arc::new(Mutex::new(available_vors.clone())),
             available_vors,
+            navaid_path: String::new(),
+            navaid_status: None,
+            navaid_report,
             selected_vor_index: 0,
-            country_filter: "All".to_string(),
+            station_query: String::new(),
+            saved_stations: SavedStations::load(&stations::stations_path()),
             display_mode: DisplayMode::Radial,
+            entry_rules: EntryRules::Icao,
         };
 
         let xplane_clone = app.xplane_data.clone();
         let holding_clone = app.holding.clone();
-        let path_clone = app.data_file_path.clone();
+        let link_clone = app.live_link.clone();
+        let vors_clone = app.shared_vors.clone();
         let ctx_clone = cc.egui_ctx.clone();
 
+        let mut clock = SimClock::realtime();
+        let mut kind = app.live_link.lock().unwrap().kind.clone();
+        let mut source = sources::create_source(&kind);
+        let _ = source.connect();
+        let mut recorder: Option<Recorder> = None;
+        let mut health = LinkHealth::default();
+        let mut last_update: Option<SystemTime> = None;
+
         std::thread::spawn(move || loop {
-            if let Ok(content) = fs::read_to_string(&path_clone) {
-                if let Ok(parsed_data) = serde_json::from_str::<XPlaneData>(&content) {
-                    if let Ok(mut xplane) = xplane_clone.lock() {
-                        *xplane = parsed_data.clone();
-
-                        if let Ok(mut holding) = holding_clone.lock() {
-                            if holding.active && xplane.vor_lat != 0.0 {
-                                let distance = calculate_distance(
-                                    xplane.aircraft_lat,
-                                    xplane.aircraft_lon,
-                                    xplane.vor_lat,
-                                    xplane.vor_lon,
-                                );
-
-                                if !holding.entry_captured && distance <= 5.0 && distance < holding.last_distance {
-                                    let bearing_to_vor = calculate_bearing(
-                                        xplane.aircraft_lat,
-                                        xplane.aircraft_lon,
-                                        xplane.vor_lat,
-                                        xplane.vor_lon,
-                                    );
+            clock.tick();
+            let (selected, recording) = {
+                let link = link_clone.lock().unwrap();
+                (link.kind.clone(), link.recording && !matches!(link.kind, SourceKind::Replay(_)))
+            };
+
+            if selected != kind {
+                source = sources::create_source(&selected);
+                let _ = source.connect();
+                kind = selected;
+                health = LinkHealth::default();
+                last_update = None;
+            }
 
-                                    let heading_diff = ((xplane.aircraft_heading - bearing_to_vor + 360.0) % 360.0).abs();
-                                    let heading_diff = if heading_diff > 180.0 { 360.0 - heading_diff } else { heading_diff };
-
-                                    if heading_diff <= 90.0 {
-                                        holding.start_heading = xplane.aircraft_heading;
-                                        holding.entry_lat = xplane.aircraft_lat;
-                                        holding.entry_lon = xplane.aircraft_lon;
-                                        holding.entry_captured = true;
-                                    }
-                                }
-
-                                holding.last_distance = distance;
-
-                                if holding.entry_captured {
-                                    holding.track_points.push(data::TrackPoint {
-                                        lat: xplane.aircraft_lat,
-                                        lon: xplane.aircraft_lon,
-                                        time: std::time::SystemTime::now()
-                                            .duration_since(std::time::UNIX_EPOCH)
-                                            .unwrap()
-                                            .as_secs_f64(),
-                                    });
-                                }
-                            }
-                        }
+            let sample = match source.poll() {
+                Ok(sample) => sample,
+                Err(_) => {
+                    let _ = source.connect();
+                    None
+                }
+            };
+            let capabilities = source.capabilities();
 
-                        ctx_clone.request_repaint();
+            if !recording {
+                recorder = None;
+            } else if recorder.is_none() {
+                recorder = Recorder::create(&sources::recording_path()).ok();
+            }
+
+            // Repeated samples (sim paused) aren't tracked, so the link goes stale instead.
+            let now = Instant::now();
+            let sample = sample.filter(|sample| health.record(sample, now));
+
+            if let Some(mut parsed_data) = sample {
+                last_update = Some(SystemTime::now());
+                // Also when the script's own navaid search came up empty.
+                let located = parsed_data.vor_lat != 0.0 || parsed_data.adf_lat != 0.0;
+                if !capabilities.station_info || !located {
+                    let vors = vors_clone.lock().unwrap().clone();
+                    sources::identify_station(&mut parsed_data, &vors);
+                }
+                magnetic::fill_missing_variation(&mut parsed_data);
+                if let Some(writer) = recorder.as_mut() {
+                    if writer.record(&parsed_data).is_err() {
+                        recorder = None;
                     }
                 }
-            }
-            std::thread::sleep(Duration::from_secs(1));
-        });
 
-        app
-    }
+                if let Ok(mut xplane) = xplane_clone.lock() {
+                    *xplane = parsed_data;
 
-    fn load_vors() -> Vec<VorInfo> {
-        let vors_data = include_str!("../vors_data.txt");
-        let mut vors = Vec::new();
-
-        for line in vors_data.lines() {
-            let parts: Vec<&str> = line.split('|').collect();
-            if parts.len() >= 6 {
-                if let (Ok(lat), Ok(lon), Ok(freq)) = (
-                    parts[3].parse::<f64>(),
-                    parts[4].parse::<f64>(),
-                    parts[5].parse::<i32>()
-                ) {
-                    vors.push(VorInfo {
-                        country: parts[0].to_string(),
-                        id: parts[1].to_string(),
-                        name: parts[2].to_string(),
-                        lat,
-                        lon,
-                        freq,
-                    });
+                    if let Ok(mut holding) = holding_clone.lock() {
+                        capture::adopt_reported_wind(&mut holding, &xplane);
+                        capture::process_sample(&mut holding, &xplane, clock.now());
+                    }
+
+                    ctx_clone.request_repaint();
                 }
             }
-        }
-        vors
+
+            if let Ok(mut link) = link_clone.lock() {
+                link.state = health.state(&source.status(), now);
+                link.last_update = last_update;
+                link.data_rate_hz = health.rate_hz(now);
+                link.capabilities = Some(capabilities);
+            }
+
+            std::thread::sleep(Duration::from_secs_f64(1.0 / capabilities.update_rate_hz));
+        });
+
+        app
     }
 
     fn generate_new_holding(&mut self) {
@@ -172,22 +202,43 @@ impl HoldingViewerApp {
         let mut rng = rand::thread_rng();
 
         if let (Ok(xplane), Ok(mut holding)) = (self.xplane_data.lock(), self.holding.lock()) {
-            if xplane.vor_id.is_empty() || xplane.vor_lat == 0.0 {
+            let vor_tuned = !xplane.vor_id.is_empty() && xplane.vor_lat != 0.0;
+            let adf_tuned = !xplane.adf_id.is_empty() && xplane.adf_lat != 0.0;
+            // With both tuned, alternate between VOR and NDB holds.
+            let (fix, radial) = if adf_tuned && (!vor_tuned || rng.gen_bool(0.5)) {
+                let station = self.tuned_station(&xplane.adf_id, xplane.adf_lat, xplane.adf_lon, NavaidKind::Ndb);
+                (HoldingFix::Station(station), rng.gen_range(0..36) * 10)
+            } else if vor_tuned {
+                let station = self.tuned_station(&xplane.vor_id, xplane.vor_lat, xplane.vor_lon, NavaidKind::Vor);
+                Self::random_holding_fix(station, &self.available_vors)
+            } else {
                 return;
-            }
+            };
+            let Some((fix_lat, fix_lon)) = resolve_fix(&fix) else {
+                return;
+            };
 
             holding.active = true;
-            holding.radial = rng.gen_range(0..36) * 10;
+            holding.fix = fix;
+            holding.fix_lat = fix_lat;
+            holding.fix_lon = fix_lon;
             holding.right_turns = rng.gen_bool(0.5);
             holding.entry_captured = false;
             holding.start_heading = 0.0;
+            holding.start_track = 0.0;
+            holding.start_relative_bearing = 0.0;
             holding.entry_lat = 0.0;
             holding.entry_lon = 0.0;
-            holding.correct_entry = String::new();
+            holding.correct_entry = None;
             holding.track_points.clear();
 
-            holding.outbound_course = holding.radial as f64;
-            holding.inbound_course = (holding.radial as f64 + 180.0) % 360.0;
+            let declination = holding.fix.declination();
+            holding.set_radial(radial, declination);
+            holding.leg = if matches!(holding.fix, HoldingFix::RadialDme { .. }) {
+                LegLength::for_dme_hold(xplane.aircraft_alt)
+            } else {
+                LegLength::for_altitude(xplane.aircraft_alt)
+            };
             holding.last_distance = 999.0;
 
             self.show_overlay = true;
@@ -195,50 +246,135 @@ impl HoldingViewerApp {
         }
     }
 
-    fn generate_simulated_position(&mut self) {
+    // Prefer the database entry so the station's published declination is used.
+    fn tuned_station(&self, id: &str, lat: f64, lon: f64, kind: NavaidKind) -> FixStation {
+        self.available_vors
+            .within_radius(lat, lon, 1.0)
+            .into_iter()
+            .find(|(vor, _)| vor.id == id)
+            .map(|(vor, _)| FixStation::from_vor(vor))
+            .unwrap_or_else(|| FixStation::new(id.to_string(), lat, lon, kind))
+    }
+
+    fn random_holding_fix(station: FixStation, available_vors: &NavaidIndex) -> (HoldingFix, i32) {
         use rand::Rng;
         let mut rng = rand::thread_rng();
-
         let radial = rng.gen_range(0..36) * 10;
-        let distance_nm = 5.0_f64;
 
-        let bearing_rad = (radial as f64).to_radians();
+        match rng.gen_range(0..4) {
+            1 => {
+                let distance = rng.gen_range(5..=20) as f64;
+                // Holding toward the station needs room for the outbound leg inside the fix.
+                let holding_radial = if distance >= 10.0 && rng.gen_bool(0.5) { (radial + 180) % 360 } else { radial };
+                (HoldingFix::RadialDme { station, radial: radial as f64, distance }, holding_radial)
+            }
+            2 => {
+                let candidates: Vec<&VorInfo> = available_vors
+                    .within_radius(station.lat, station.lon, 80.0)
+                    .into_iter()
+                    .filter(|(vor, distance)| vor.kind.has_radials() && vor.id != station.id && *distance >= 10.0)
+                    .map(|(vor, _)| vor)
+                    .collect();
+
+                if !candidates.is_empty() {
+                    for _ in 0..10 {
+                        let second = candidates[rng.gen_range(0..candidates.len())];
+                        let distance = rng.gen_range(8..=20) as f64;
+                        let second = FixStation::from_vor(second);
+                        let (lat, lon) = destination_point(station.lat, station.lon, magnetic_to_true(radial as f64, station.declination), distance);
+                        let bearing = calculate_bearing(second.lat, second.lon, lat, lon);
+                        let second_radial = true_to_magnetic(bearing, second.declination).round() % 360.0;
+
+                        let crossing = angle_difference(radial as f64, second_radial).abs();
+                        if (30.0..=150.0).contains(&crossing) {
+                            let fix = HoldingFix::Intersection {
+                                first: station,
+                                first_radial: radial as f64,
+                                second,
+                                second_radial,
+                            };
+                            return (fix, radial);
+                        }
+                    }
+                }
+                (HoldingFix::Station(station), radial)
+            }
+            3 => {
+                const CONSONANTS: &[u8] = b"BCDFGKLMNPRSTVZ";
+                const VOWELS: &[u8] = b"AEIOU";
+                let name: String = (0..5)
+                    .map(|i| {
+                        let letters = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+                        letters[rng.gen_range(0..letters.len())] as char
+                    })
+                    .collect();
+
+                let bearing = rng.gen_range(0..360) as f64;
+                let distance = rng.gen_range(5..=15) as f64;
+                let (lat, lon) = destination_point(station.lat, station.lon, bearing, distance);
+                (HoldingFix::Waypoint { name, lat, lon }, rng.gen_range(0..36) * 10)
+            }
+            _ => (HoldingFix::Station(station), radial),
+        }
+    }
 
-        let angular_distance: f64 = distance_nm / 3440.065;
-        let vor_lat_rad = self.simulated_data.vor_lat.to_radians();
-        let vor_lon_rad = self.simulated_data.vor_lon.to_radians();
+    fn generate_simulated_position(&mut self) {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
 
-        let new_lat_rad = (vor_lat_rad.sin() * angular_distance.cos() +
-                          vor_lat_rad.cos() * angular_distance.sin() * bearing_rad.cos()).asin();
+        let navaid = &self.available_vors.navaids()[self.selected_vor_index];
+        let station = FixStation::from_vor(navaid);
+        // NDBs and DMEs have no radials to build a fix from: hold over the station itself.
+        let (fix, holding_radial) = if navaid.kind.has_radials() {
+            Self::random_holding_fix(station, &self.available_vors)
+        } else {
+            (HoldingFix::Station(station), rng.gen_range(0..36) * 10)
+        };
+        let (fix_lat, fix_lon) = resolve_fix(&fix).unwrap_or((self.simulated_data.vor_lat, self.simulated_data.vor_lon));
 
-        let new_lon_rad = vor_lon_rad +
-                         (bearing_rad.sin() * angular_distance.sin() * vor_lat_rad.cos())
-                         .atan2(angular_distance.cos() - vor_lat_rad.sin() * new_lat_rad.sin());
+        let radial = rng.gen_range(0..36) * 10;
+        let distance_nm = 8.0_f64;
 
-        self.simulated_data.aircraft_lat = new_lat_rad.to_degrees();
-        self.simulated_data.aircraft_lon = new_lon_rad.to_degrees();
+        let (lat, lon) = destination_point(fix_lat, fix_lon, radial as f64, distance_nm);
+        self.simulated_data.aircraft_lat = lat;
+        self.simulated_data.aircraft_lon = lon;
 
-        let heading_to_vor = calculate_bearing(
+        let heading_to_fix = calculate_bearing(
             self.simulated_data.aircraft_lat,
             self.simulated_data.aircraft_lon,
-            self.simulated_data.vor_lat,
-            self.simulated_data.vor_lon,
+            fix_lat,
+            fix_lon,
         );
-        self.simulated_data.aircraft_heading = heading_to_vor;
+        self.simulated_data.aircraft_heading = heading_to_fix;
         self.simulated_data.aircraft_alt = 8000.0;
-        self.simulated_data.aircraft_groundspeed = 180.0;
+        let wind = self.simulated_holding.wind.wind_at(self.simulated_data.aircraft_alt);
+        let (track, groundspeed) = ground_track(heading_to_fix, self.simulated_holding.true_airspeed, wind);
+        self.simulated_data.aircraft_groundspeed = groundspeed;
+
+        self.flight_model = FlightModel {
+            max_bank: self.simulated_holding.bank_angle,
+            groundspeed,
+            track,
+            ..FlightModel::new(lat, lon, self.simulated_data.aircraft_alt, heading_to_fix, self.simulated_holding.true_airspeed)
+        };
+        self.flight_model.write_to(&mut self.simulated_data, wind);
+        self.scrub_time = None;
 
         self.simulated_holding.active = true;
-        self.simulated_holding.radial = rng.gen_range(0..36) * 10;
+        self.simulated_holding.fix = fix;
+        self.simulated_holding.fix_lat = fix_lat;
+        self.simulated_holding.fix_lon = fix_lon;
         self.simulated_holding.right_turns = rng.gen_bool(0.5);
         self.simulated_holding.entry_captured = false;
         self.simulated_holding.start_heading = 0.0;
+        self.simulated_holding.start_track = 0.0;
+        self.simulated_holding.start_relative_bearing = 0.0;
         self.simulated_holding.entry_lat = 0.0;
         self.simulated_holding.entry_lon = 0.0;
-        self.simulated_holding.correct_entry = String::new();
+        self.simulated_holding.correct_entry = None;
         self.simulated_holding.track_points.clear();
-        self.simulated_holding.outbound_course = self.simulated_holding.radial as f64;
-        self.simulated_holding.inbound_course = (self.simulated_holding.radial as f64 + 180.0) % 360.0;
+        let declination = self.simulated_holding.fix.declination();
+        self.simulated_holding.set_radial(holding_radial, declination);
         self.simulated_holding.last_distance = 999.0;
 
         self.show_overlay = true;
@@ -251,11 +387,12 @@ impl HoldingViewerApp {
                 return;
             }
 
-            holding.correct_entry = calculate_entry_type(
-                holding.start_heading,
+            holding.correct_entry = Some(calculate_entry_type(
+                holding.entry_reference(),
                 holding.inbound_course,
-                holding.right_turns
-            );
+                holding.right_turns,
+                self.entry_rules,
+            ));
         }
     }
 
@@ -264,29 +401,149 @@ impl HoldingViewerApp {
             return;
         }
 
-        self.simulated_holding.start_heading = self.simulated_data.aircraft_heading;
+        if self.simulated_holding.entry_captured {
+            self.simulated_holding.correct_entry = Some(calculate_entry_type(
+                self.simulated_holding.entry_reference(),
+                self.simulated_holding.inbound_course,
+                self.simulated_holding.right_turns,
+                self.entry_rules,
+            ));
+            return;
+        }
+
+        let wind = self.simulated_holding.wind.wind_at(self.simulated_data.aircraft_alt);
+        let (track, groundspeed) = ground_track(
+            self.simulated_data.aircraft_heading_true,
+            self.simulated_holding.true_airspeed,
+            wind,
+        );
+        self.simulated_data.aircraft_groundspeed = groundspeed;
+
+        self.simulated_holding.start_heading = self.simulated_data.aircraft_heading_true;
+        self.simulated_holding.start_track = track;
+        let bearing_to_fix = calculate_bearing(
+            self.simulated_data.aircraft_lat,
+            self.simulated_data.aircraft_lon,
+            self.simulated_holding.fix_lat,
+            self.simulated_holding.fix_lon,
+        );
+        self.simulated_holding.start_relative_bearing = relative_bearing(self.simulated_data.aircraft_heading_true, bearing_to_fix);
         self.simulated_holding.entry_lat = self.simulated_data.aircraft_lat;
         self.simulated_holding.entry_lon = self.simulated_data.aircraft_lon;
         self.simulated_holding.entry_captured = true;
 
-        self.simulated_holding.correct_entry = calculate_entry_type(
-            self.simulated_holding.start_heading,
+        self.simulated_holding.correct_entry = Some(calculate_entry_type(
+            self.simulated_holding.entry_reference(),
             self.simulated_holding.inbound_course,
-            self.simulated_holding.right_turns
+            self.simulated_holding.right_turns,
+            self.entry_rules,
+        ));
+    }
+
+    fn step_simulation(&mut self) {
+        let dt = self.sim_clock.tick();
+
+        if !self.simulated_holding.active || self.simulated_data.aircraft_lat == 0.0 {
+            return;
+        }
+
+        if let Some(time) = self.scrub_time {
+            if !self.sim_clock.paused {
+                self.rewind_simulation(time);
+                self.scrub_time = None;
+            }
+            return;
+        }
+        if dt <= 0.0 {
+            return;
+        }
+
+        let wind = self.simulated_holding.wind.wind_at(self.flight_model.altitude);
+        self.flight_model.target_airspeed = self.simulated_holding.true_airspeed;
+        self.flight_model.max_bank = self.simulated_holding.bank_angle;
+        self.flight_model.step(dt, wind);
+        self.flight_model.write_to(&mut self.simulated_data, wind);
+
+        capture::process_sample(&mut self.simulated_holding, &self.simulated_data, self.sim_clock.now());
+    }
+
+    // Drops everything recorded after `time` and puts the aircraft back on that track point.
+    fn rewind_simulation(&mut self, time: f64) {
+        self.simulated_holding = self.simulated_holding.truncated_at(time);
+        let point = match self.simulated_holding.track_points.last() {
+            Some(point) => point.clone(),
+            None => return,
+        };
+
+        self.flight_model = self.flight_model.resume_from(&point);
+        let wind = self.simulated_holding.wind.wind_at(point.alt);
+        self.flight_model.write_to(&mut self.simulated_data, wind);
+
+        self.simulated_holding.last_distance = calculate_distance(
+            point.lat,
+            point.lon,
+            self.simulated_holding.fix_lat,
+            self.simulated_holding.fix_lon,
         );
+        self.sim_clock.rewind_to(point.time);
+    }
+
+    fn reclassify_entries(&mut self) {
+        if self.simulated_holding.correct_entry.is_some() {
+            self.simulated_holding.correct_entry = Some(calculate_entry_type(
+                self.simulated_holding.entry_reference(),
+                self.simulated_holding.inbound_course,
+                self.simulated_holding.right_turns,
+                self.entry_rules,
+            ));
+        }
+
+        if let Ok(mut holding) = self.holding.lock() {
+            let (reference, inbound_course, right_turns) = (holding.entry_reference(), holding.inbound_course, holding.right_turns);
+            if let Some(entry) = holding.correct_entry.as_mut() {
+                *entry = calculate_entry_type(reference, inbound_course, right_turns, self.entry_rules);
+            }
+        }
+    }
+
+    fn import_navaids(&mut self) {
+        let path = self.navaid_path.trim();
+        if path.is_empty() {
+            return;
+        }
+        match navdata::import_earth_nav(&navdata::data_directory(), Path::new(path)) {
+            Ok((navaids, report)) => {
+                self.navaid_status = Some(format!("{} navaids available", navaids.len()));
+                let current = StationKey::of(&self.available_vors.navaids()[self.selected_vor_index]);
+                self.available_vors = Arc::new(NavaidIndex::new(navaids));
+                self.navaid_report = report;
+                *self.shared_vors.lock().unwrap() = self.available_vors.clone();
+                self.station_query.clear();
+                // Stay on the selected station if the import kept it.
+                self.change_selected_vor(self.available_vors.find_station(&current.id, current.lat, current.lon, current.ndb).unwrap_or(0));
+            }
+            Err(e) => self.navaid_status = Some(format!("Import failed: {}", e)),
+        }
     }
 
     fn change_selected_vor(&mut self, vor_index: usize) {
-        if vor_index < self.available_vors.len() {
-            let vor = &self.available_vors[vor_index];
+        if vor_index < self.available_vors.navaids().len() {
+            let vor = &self.available_vors.navaids()[vor_index];
             self.simulated_data.vor_id = vor.id.clone();
             self.simulated_data.vor_freq = vor.freq;
             self.simulated_data.vor_lat = vor.lat;
             self.simulated_data.vor_lon = vor.lon;
+            // An NDB is also what ADF1 is tuned to, so the RMI needle points at it.
+            let ndb = vor.kind == NavaidKind::Ndb;
+            self.simulated_data.adf_id = if ndb { vor.id.clone() } else { String::new() };
+            self.simulated_data.adf_freq = if ndb { vor.freq } else { 0 };
+            self.simulated_data.adf_lat = if ndb { vor.lat } else { 0.0 };
+            self.simulated_data.adf_lon = if ndb { vor.lon } else { 0.0 };
             self.selected_vor_index = vor_index;
             self.simulated_data.aircraft_lat = 0.0;
             self.simulated_data.aircraft_lon = 0.0;
             self.simulated_holding = HoldingPattern::default();
+            self.scrub_time = None;
             self.map_offset = egui::Vec2::ZERO;
         }
     }
@@ -320,12 +577,14 @@ impl eframe::App for HoldingViewerApp {
             ui::dialogs::draw_how_to_fly_dialog(ctx, &mut self.show_how_it_works);
         }
 
+        let previous_rules = self.entry_rules;
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut self.active_tab, Tab::Simulate,
                     egui::RichText::new("SIMULATE").size(14.0));
-                ui.selectable_value(&mut self.active_tab, Tab::XPlane11,
-                    egui::RichText::new("X-PLANE 11 CONNECT").size(14.0));
+                ui.selectable_value(&mut self.active_tab, Tab::Live,
+                    egui::RichText::new("LIVE").size(14.0));
             });
 
             ui.add_space(5.0);
@@ -334,21 +593,48 @@ impl eframe::App for HoldingViewerApp {
 
             match self.active_tab {
                 Tab::Simulate => {
+                    if self.simulated_holding.active {
+                        ui::telemetry::handle_fly_keys(ctx, &mut self.flight_model, &mut self.sim_clock);
+                    }
+                    self.step_simulation();
+
+                    let scrubbed = self.scrub_time.and_then(|time| {
+                        let holding = self.simulated_holding.truncated_at(time);
+                        let point = holding.track_points.last()?.clone();
+                        let data = XPlaneData {
+                            aircraft_lat: point.lat,
+                            aircraft_lon: point.lon,
+                            aircraft_alt: point.alt,
+                            aircraft_heading: true_to_magnetic(point.heading, self.simulated_data.magnetic_variation),
+                            aircraft_heading_true: point.heading,
+                            aircraft_groundspeed: point.groundspeed,
+                            bank_angle: point.bank.unwrap_or(0.0),
+                            indicated_airspeed: point.indicated_airspeed.unwrap_or(0.0),
+                            ..self.simulated_data.clone()
+                        };
+                        Some((data, holding))
+                    });
+                    let (map_data, map_holding) = scrubbed
+                        .unwrap_or_else(|| (self.simulated_data.clone(), self.simulated_holding.clone()));
+
                     let mut actions = ui::telemetry::SimulatedTelemetryActions {
                         generate_position: false,
                         calculate_result: false,
                         change_vor: None,
+                        toggle_favourite: None,
+                        import_navaids: false,
                     };
+                    let mut map_actions = ui::map::MapActions { steer_to: None };
 
                     ui.horizontal_top(|ui| {
                         let available_width = ui.available_width() - 315.0;
 
                         ui.vertical(|ui| {
                             ui.set_width(available_width);
-                            ui::map::draw_map(
+                            map_actions = ui::map::draw_map(
                                 ui,
-                                &self.simulated_data,
-                                &self.simulated_holding,
+                                &map_data,
+                                &map_holding,
                                 &self.tile_manager,
                                 self.zoom,
                                 self.show_overlay,
@@ -356,6 +642,8 @@ impl eframe::App for HoldingViewerApp {
                                 &mut self.is_dragging,
                                 &mut self.drag_start,
                                 self.display_mode,
+                                self.entry_rules,
+                                Some(self.flight_model.heading_bug),
                             );
                         });
 
@@ -369,18 +657,30 @@ impl eframe::App for HoldingViewerApp {
                                 .show(ui, |ui| {
                                     actions = ui::telemetry::draw_simulated_telemetry(
                                         ui,
-                                        &mut self.simulated_data,
-                                        &self.simulated_holding,
+                                        &self.simulated_data,
+                                        &mut self.simulated_holding,
+                                        &mut self.flight_model,
+                                        &mut self.sim_clock,
+                                        &mut self.scrub_time,
                                         &self.available_vors,
                                         self.selected_vor_index,
-                                        &mut self.country_filter,
+                                        &mut self.navaid_path,
+                                        &self.navaid_status,
+                                        &self.navaid_report,
+                                        &mut self.station_query,
+                                        &self.saved_stations,
                                         &mut self.zoom,
                                         &mut self.display_mode,
+                                        &mut self.entry_rules,
                                     );
                                 });
                         });
                     });
 
+                    if let Some((lat, lon)) = map_actions.steer_to {
+                        let bearing = calculate_bearing(self.simulated_data.aircraft_lat, self.simulated_data.aircraft_lon, lat, lon);
+                        self.flight_model.set_heading_bug(bearing);
+                    }
                     if actions.generate_position {
                         self.generate_simulated_position();
                     }
@@ -389,13 +689,30 @@ impl eframe::App for HoldingViewerApp {
                     }
                     if let Some(idx) = actions.change_vor {
                         self.change_selected_vor(idx);
+                        self.saved_stations.record_recent(&self.available_vors.navaids()[idx]);
+                        let _ = self.saved_stations.save(&stations::stations_path());
+                    }
+                    if let Some(idx) = actions.toggle_favourite {
+                        self.saved_stations.toggle_favourite(&self.available_vors.navaids()[idx]);
+                        let _ = self.saved_stations.save(&stations::stations_path());
+                    }
+                    if actions.import_navaids {
+                        self.import_navaids();
                     }
                 },
-                Tab::XPlane11 => {
+                Tab::Live => {
+                    let mut live_link = self.live_link.lock().unwrap().clone();
                     let mut actions = ui::telemetry::TelemetryActions {
                         generate_holding: false,
                         calculate_result: false,
                     };
+                    // What to tune before a hold is set up; no position means no sim yet.
+                    let navaids = self.available_vors.clone();
+                    let nearby_navaids = if !holding.active && xplane.aircraft_lat != 0.0 {
+                        navaids.nearest(xplane.aircraft_lat, xplane.aircraft_lon, 5)
+                    } else {
+                        Vec::new()
+                    };
 
                     ui.horizontal_top(|ui| {
                         let available_width = ui.available_width() - 315.0;
@@ -413,6 +730,8 @@ impl eframe::App for HoldingViewerApp {
                                 &mut self.is_dragging,
                                 &mut self.drag_start,
                                 self.display_mode,
+                                self.entry_rules,
+                                None,
                             );
                         });
 
@@ -430,11 +749,20 @@ impl eframe::App for HoldingViewerApp {
                                         &holding,
                                         &mut self.zoom,
                                         &mut self.display_mode,
+                                        &mut self.entry_rules,
+                                        &mut live_link,
+                                        &mut self.source_settings,
+                                        &nearby_navaids,
                                     );
                                 });
                         });
                     });
 
+                    // Status is the polling thread's to report; only the choices go back.
+                    if let Ok(mut link) = self.live_link.lock() {
+                        link.kind = live_link.kind;
+                        link.recording = live_link.recording;
+                    }
                     if actions.generate_holding {
                         self.generate_new_holding();
                     }
@@ -445,6 +773,10 @@ impl eframe::App for HoldingViewerApp {
             }
         });
 
+        if self.entry_rules != previous_rules {
+            self.reclassify_entries();
+        }
+
         ctx.request_repaint_after(Duration::from_millis(100));
     }
 }
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::calculations::calculate_distance;
+use crate::data::{NavaidKind, VorInfo};
+use crate::magnetic;
+
+const NDB: u32 = 2;
+const VOR: u32 = 3;
+const PAIRED_DME: u32 = 12;
+const STANDALONE_DME: u32 = 13;
+// A DME this close to a VOR on the same frequency is the VOR's own DME.
+const PAIRED_DME_NM: f64 = 1.0;
+// Files before 1200 don't say which country a navaid is in.
+const UNKNOWN_REGION: &str = "Unknown";
+
+pub const DATA_DIR_VAR: &str = "HOLDING_TRAINER_DATA";
+const DEFAULT_DATA_DIR: &str = "navdata";
+const BUILT_IN_VORS: &str = include_str!("../vors_data.txt");
+const VOR_FREQUENCIES: std::ops::RangeInclusive<i32> = 10800..=11795;
+const NDB_FREQUENCIES: std::ops::RangeInclusive<i32> = 190..=1750;
+// VHF frequencies are written in 10 kHz units (five digits); anything below this is
+// an NDB frequency in kHz.
+const VHF_FREQUENCY_MIN: i32 = 10_000;
+
+// Files read at startup and every problem found in them, shown in the VOR panel.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub sources: Vec<String>,
+    pub issues: Vec<String>,
+}
+
+pub fn data_directory() -> PathBuf {
+    env::var(DATA_DIR_VAR)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_DATA_DIR), PathBuf::from)
+}
+
+// The built-in list, then every *.txt file in `directory` in name order. A station
+// with the same ident and country as an earlier one replaces it, so a school can
+// correct or add its local navaids without rebuilding.
+pub fn load_navaid_database(directory: &Path) -> (Vec<VorInfo>, ValidationReport) {
+    let mut report = ValidationReport::default();
+    let mut navaids = Vec::new();
+    let mut origins = Vec::new();
+    merge(&mut navaids, &mut origins, parse_vor_list(BUILT_IN_VORS, "built-in", &mut report), "built-in", &mut report);
+    report.sources.push(format!("built-in ({} stations)", navaids.len()));
+
+    let mut files: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("txt")))
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            report.issues.push(format!("{}: {}", directory.display(), e));
+            Vec::new()
+        }
+    };
+    files.sort();
+
+    for path in files {
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let parsed = parse_vor_list(&String::from_utf8_lossy(&bytes), &name, &mut report);
+                report.sources.push(format!("{} ({} stations)", name, parsed.len()));
+                merge(&mut navaids, &mut origins, parsed, &name, &mut report);
+            }
+            Err(e) => report.issues.push(format!("{}: {}", name, e)),
+        }
+    }
+    // Every station without a published declination falls back to the model.
+    report.issues.extend(magnetic::model_warning());
+    (navaids, report)
+}
+
+fn merge(navaids: &mut Vec<VorInfo>, origins: &mut Vec<String>, parsed: Vec<(usize, VorInfo)>, source: &str, report: &mut ValidationReport) {
+    for (line, vor) in parsed {
+        let origin = format!("{}:{}", source, line);
+        match navaids.iter().position(|existing| existing.id == vor.id && existing.country == vor.country) {
+            Some(index) => {
+                report.issues.push(format!("{}: duplicate ident {} ({}) replaces {}", origin, vor.id, vor.country, origins[index]));
+                navaids[index] = vor;
+                origins[index] = origin;
+            }
+            None => {
+                navaids.push(vor);
+                origins.push(origin);
+            }
+        }
+    }
+}
+
+// country|ident|name|lat|lon|freq[|declination], one station per line, with the
+// frequency in 10 kHz units for VORs (11645) and in kHz for NDBs (380); blank lines
+// and lines starting with # are ignored. Returns the accepted stations with their
+// line numbers; everything else goes to the report.
+fn parse_vor_list(content: &str, source: &str, report: &mut ValidationReport) -> Vec<(usize, VorInfo)> {
+    let mut vors = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_vor_line(line) {
+            Ok(vor) => {
+                // Worth a look but still usable, so the station stays in.
+                if vor.kind == NavaidKind::Ndb && !NDB_FREQUENCIES.contains(&vor.freq) {
+                    report.issues.push(format!("{}:{}: NDB frequency {} outside 190-1750 kHz", source, line_number, vor.freq));
+                }
+                if vor.kind != NavaidKind::Ndb && !VOR_FREQUENCIES.contains(&vor.freq) {
+                    report.issues.push(format!("{}:{}: frequency {} outside 108.00-117.95 MHz", source, line_number, vor.freq));
+                }
+                vors.push((line_number, vor));
+            }
+            Err(problem) => report.issues.push(format!("{}:{}: skipped, {}", source, line_number, problem)),
+        }
+    }
+    vors
+}
+
+fn parse_vor_line(line: &str) -> Result<VorInfo, String> {
+    let parts: Vec<&str> = line.split('|').map(str::trim).collect();
+    if parts.len() < 6 {
+        return Err("expected country|ident|name|lat|lon|freq".to_string());
+    }
+    if parts[1].is_empty() {
+        return Err("missing ident".to_string());
+    }
+
+    let lat = parts[3].parse::<f64>().map_err(|_| format!("bad latitude '{}'", parts[3]))?;
+    let lon = parts[4].parse::<f64>().map_err(|_| format!("bad longitude '{}'", parts[4]))?;
+    let freq = parts[5].parse::<i32>().map_err(|_| format!("bad frequency '{}'", parts[5]))?;
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("latitude {} out of range", lat));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(format!("longitude {} out of range", lon));
+    }
+
+    // Optional 7th column: the published station declination. Without it the
+    // current variation from the model is the best guess.
+    let declination = match parts.get(6).filter(|field| !field.is_empty()) {
+        Some(field) => magnetic::parse_declination(field).ok_or_else(|| format!("bad declination '{}'", field))?,
+        None => magnetic::declination_now(lat, lon),
+    };
+
+    Ok(VorInfo {
+        country: parts[0].to_string(),
+        id: parts[1].to_string(),
+        name: parts[2].to_string(),
+        lat,
+        lon,
+        freq,
+        declination,
+        kind: if freq < VHF_FREQUENCY_MIN { NavaidKind::Ndb } else { NavaidKind::Vor },
+    })
+}
+
+// The database from `directory` with an earth_nav.dat on top. An imported navaid
+// replaces the listed station it is (see VorInfo::is_station); the file's own navaids
+// all stay. Importing again starts from the directory, so the new file replaces the
+// previous import.
+pub fn import_earth_nav(directory: &Path, path: &Path) -> io::Result<(Vec<VorInfo>, ValidationReport)> {
+    let imported = load_earth_nav(path)?;
+    let (mut navaids, mut report) = load_navaid_database(directory);
+    let mut listed: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, vor) in navaids.iter().enumerate() {
+        listed.entry(vor.id.clone()).or_default().push(index);
+    }
+
+    let total = imported.len();
+    let mut replaced = 0;
+    for navaid in imported {
+        let ndb = navaid.kind == NavaidKind::Ndb;
+        // Each listed station is replaced once; later matches are kept as new navaids.
+        let candidates = listed.get_mut(&navaid.id);
+        let found = candidates.as_ref().and_then(|candidates| {
+            candidates.iter().position(|&index| navaids[index].is_station(&navaid.id, navaid.lat, navaid.lon, ndb))
+        });
+        match (candidates, found) {
+            (Some(candidates), Some(position)) => {
+                let index = candidates.remove(position);
+                navaids[index] = navaid;
+                replaced += 1;
+            }
+            _ => navaids.push(navaid),
+        }
+    }
+    let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    report.sources.push(format!("{} ({} navaids, {} replacing listed stations)", name, total, replaced));
+    Ok((navaids, report))
+}
+
+// Loads X-Plane's earth_nav.dat (Resources/default data or Custom Data). Only the
+// en-route navaids are kept; localizers, glideslopes and markers are skipped.
+pub fn load_earth_nav(path: &Path) -> io::Result<Vec<VorInfo>> {
+    // Older files carry Latin-1 names, which would make read_to_string fail.
+    let bytes = fs::read(path)?;
+    parse_earth_nav(&String::from_utf8_lossy(&bytes)).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+// Versions 810, 1100 and 1200 share the leading columns; 1200 adds the terminal
+// region and ICAO region code between the ident and the name.
+pub fn parse_earth_nav(content: &str) -> Result<Vec<VorInfo>, String> {
+    let mut lines = content.lines();
+    let _origin = lines.next();
+    let version = lines
+        .next()
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|word| word.parse::<u32>().ok())
+        .ok_or("not an earth_nav.dat file")?;
+    if !matches!(version, 810 | 1100 | 1200) {
+        return Err(format!("unsupported earth_nav.dat version {}", version));
+    }
+
+    let mut navaids: Vec<VorInfo> = lines.filter_map(|line| parse_record(line, version)).collect();
+    if navaids.is_empty() {
+        return Err("no VOR, NDB or DME records found".to_string());
+    }
+
+    let vhf_stations: HashMap<(String, i32), (f64, f64)> = navaids
+        .iter()
+        .filter(|vor| matches!(vor.kind, NavaidKind::VorDme | NavaidKind::Vortac))
+        .map(|vor| ((vor.id.clone(), vor.freq), (vor.lat, vor.lon)))
+        .collect();
+    navaids.retain(|dme| {
+        dme.kind != NavaidKind::Dme
+            || !vhf_stations
+                .get(&(dme.id.clone(), dme.freq))
+                .is_some_and(|&(lat, lon)| calculate_distance(lat, lon, dme.lat, dme.lon) < PAIRED_DME_NM)
+    });
+    Ok(navaids)
+}
+
+fn parse_record(line: &str, version: u32) -> Option<VorInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let code = fields.first()?.parse::<u32>().ok()?;
+    if !matches!(code, NDB | VOR | PAIRED_DME | STANDALONE_DME) {
+        return None;
+    }
+
+    let name_start = if version >= 1200 { 10 } else { 8 };
+    if fields.len() <= name_start {
+        return None;
+    }
+    let lat = fields[1].parse::<f64>().ok()?;
+    let lon = fields[2].parse::<f64>().ok()?;
+    let freq = fields[4].parse::<i32>().ok()?;
+    let slaved_variation = fields[6].parse::<f64>().ok()?;
+    let id = fields[7].to_string();
+    let country = if version >= 1200 { fields[9] } else { UNKNOWN_REGION }.to_string();
+
+    // The last word of the name is the navaid type, e.g. "SEATTLE VORTAC".
+    let suffix = fields[fields.len() - 1];
+    let kind = match code {
+        NDB => NavaidKind::Ndb,
+        VOR => match suffix {
+            "VORTAC" => NavaidKind::Vortac,
+            "VOR-DME" => NavaidKind::VorDme,
+            _ => NavaidKind::Vor,
+        },
+        _ if suffix == "DME-ILS" => return None,
+        _ => NavaidKind::Dme,
+    };
+
+    let declination = if code == VOR { slaved_variation } else { magnetic::declination_now(lat, lon) };
+    let name_end = if fields.len() - name_start > 1 { fields.len() - 1 } else { fields.len() };
+
+    Some(VorInfo {
+        country,
+        id,
+        name: fields[name_start..name_end].join(" "),
+        lat,
+        lon,
+        freq,
+        declination,
+        kind,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Region codes instead of the built-in list's country names, and the antennas rather
+    // than the built-in list's positions.
+    const EARTH_NAV_1200: &str = "I\n1200 Version - data cycle 2101\n\
+        3  40.46913900   -3.55752800    1998 11645 130   -1.000 BRA  ENRT LE BARAJAS VOR-DME\n\
+        3  47.43537200 -122.30961700     354 11680 130   19.000 SEA  ENRT K1 SEATTLE VORTAC\n\
+        3  33.93309700 -118.43200000     185 11360 130   15.000 LAX  ENRT K2 LOS ANGELES VORTAC\n\
+        3  37.61948300 -122.37389100      13 11580 130   17.000 SFO  ENRT K2 SAN FRANCISCO VOR-DME\n\
+        2  40.47000000   -3.56000000    2000   320  25    0.000 BRA  ENRT LE BARAJAS NDB\n\
+        3 -22.80000000  -43.25000000      20 11290 130  -21.000 BRA  ENRT SB BRASILIA VOR-DME\n\
+        2  40.25000000   -3.70000000    2000   380  25    0.000 PDT  ENRT LE PINTO NDB\n\
+        2  40.26000000   -3.71000000    2000   390  25    0.000 PDT  ENRT LE PINTO 2 NDB\n\
+        99\n";
+
+    #[test]
+    fn reads_every_supported_version() {
+        let v810 = "I\n810 Version - data cycle 2013.10\n\
+            2  47.392500 -122.386944      0   362  25    0.0 SZ   SEATTLE NDB\n\
+            3  47.435372 -122.309617    354 11680 130   19.0 SEA  SEATTLE VORTAC\n\
+            4  47.463890 -122.307750    425 11030  18   179.8 ISNQ KSEA 16L ILS-cat-III\n\
+            12  47.435372 -122.309617    354 11680 130    0.0 SEA  SEATTLE VORTAC DME\n\
+            12  47.4382  -122.3110      425 11030  18    0.0 ISNQ KSEA 16L DME-ILS\n\
+            99\n";
+        let navaids = parse_earth_nav(v810).unwrap();
+        assert_eq!(navaids.len(), 2);
+        assert_eq!((navaids[0].kind, navaids[0].freq, navaids[0].name.as_str()), (NavaidKind::Ndb, 362, "SEATTLE"));
+        assert_eq!((navaids[1].kind, navaids[1].freq, navaids[1].declination), (NavaidKind::Vortac, 11680, 19.0));
+
+        let v1100 = "I\n1100 Version - data cycle 1802\n\
+            3  40.46913900   -3.55752800    1998 11645 130   -1.000 BRA  BARAJAS VOR-DME\n\
+            13  40.30000000   -3.70000000    2000 11500  40    0.000 GTF  GETAFE DME\n\
+            99\n";
+        let navaids = parse_earth_nav(v1100).unwrap();
+        assert_eq!((navaids[0].kind, navaids[0].declination), (NavaidKind::VorDme, -1.0));
+        assert_eq!((navaids[1].kind, navaids[1].id.as_str()), (NavaidKind::Dme, "GTF"));
+
+        let v1200 = "I\n1200 Version - data cycle 2101\n\
+            3  61.15000000 -150.20000000     200 11430 130   23.000 ANC  ENRT PA ANCHORAGE VOR/DME VOR-DME\n\
+            99\n";
+        let navaids = parse_earth_nav(v1200).unwrap();
+        assert_eq!((navaids[0].country.as_str(), navaids[0].name.as_str()), ("PA", "ANCHORAGE VOR/DME"));
+
+        assert!(parse_earth_nav("I\n740 Version\n99\n").is_err());
+        assert!(parse_earth_nav("not a navaid file").is_err());
+    }
+
+    #[test]
+    fn merges_user_files_and_reports_problems() {
+        let directory = std::env::temp_dir().join(format!("holding_navdata_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("school.txt"),
+            "# Our local stations\n\
+             Spain|XYZ|Escuela|40.1|-3.9|11300|1W\n\
+             Spain|BRA|Barajas|40.469139|-3.557528|11645|1W\n\
+             Spain|BAD|Broken|40.1\n\
+             Spain|FAR|Far away|95.0|-3.9|11300\n\
+             Spain|HFR|High freq|40.1|-3.9|12500\n\
+             Spain|XYZ|Escuela 2|40.2|-3.9|11310\n\
+             Spain|PDT|Pinto|40.25|-3.7|380\n\
+             Spain|LOW|Low NDB|40.3|-3.7|150\n",
+        )
+        .unwrap();
+        fs::write(directory.join("notes.md"), "not navaid data").unwrap();
+
+        let (navaids, report) = load_navaid_database(&directory);
+        let built_in = parse_vor_list(BUILT_IN_VORS, "built-in", &mut ValidationReport::default()).len();
+        assert_eq!(navaids.len(), built_in + 4);
+        let pdt = navaids.iter().find(|vor| vor.id == "PDT").unwrap();
+        assert_eq!((pdt.kind, pdt.frequency_label()), (NavaidKind::Ndb, "380 kHz".to_string()));
+        let bra: Vec<&VorInfo> = navaids.iter().filter(|vor| vor.id == "BRA").collect();
+        assert_eq!((bra.len(), bra[0].declination, bra[0].frequency_label()), (1, -1.0, "116.45 MHz".to_string()));
+        assert_eq!(navaids.iter().find(|vor| vor.id == "XYZ").unwrap().name, "Escuela 2");
+
+        assert_eq!(report.sources.len(), 2);
+        let expected = [
+            "school.txt:3: duplicate ident BRA (Spain) replaces built-in:",
+            "school.txt:4: skipped, expected",
+            "school.txt:5: skipped, latitude 95",
+            "school.txt:6: frequency 12500",
+            "school.txt:7: duplicate ident XYZ (Spain) replaces school.txt:2",
+            "school.txt:9: NDB frequency 150 outside 190-1750 kHz",
+        ];
+        let mut issues: Vec<&String> = report.issues.iter().filter(|issue| issue.starts_with("school.txt")).collect();
+        issues.sort();
+        assert_eq!(issues.len(), expected.len(), "{:?}", issues);
+        for (issue, prefix) in issues.into_iter().zip(expected) {
+            assert!(issue.starts_with(prefix), "{}", issue);
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(load_navaid_database(&directory).0.len(), built_in);
+    }
+
+    #[test]
+    fn imports_earth_nav_on_top_of_the_listed_stations() {
+        let directory = std::env::temp_dir().join(format!("holding_import_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("school.txt"), "Spain|XYZ|Escuela|40.1|-3.9|11300|1W\n").unwrap();
+        let earth_nav = directory.join("earth_nav.dat");
+        fs::write(&earth_nav, EARTH_NAV_1200).unwrap();
+
+        let (listed, _) = load_navaid_database(&directory);
+        let (navaids, report) = import_earth_nav(&directory, &earth_nav).unwrap();
+        // The built-in BRA, SEA, LAX and SFO are replaced; the NDB at Barajas, the other
+        // BRA far away and both PDTs are new.
+        assert_eq!(navaids.len(), listed.len() + 4);
+        let bra: Vec<&VorInfo> = navaids.iter().filter(|vor| vor.id == "BRA" && vor.kind != NavaidKind::Ndb && vor.lat > 0.0).collect();
+        assert_eq!((bra.len(), bra[0].country.as_str(), bra[0].kind), (1, "LE", NavaidKind::VorDme));
+        assert!(!navaids.iter().any(|vor| vor.country == "Spain" && vor.id == "BRA"));
+        let sea: Vec<&VorInfo> = navaids.iter().filter(|vor| vor.id == "SEA").collect();
+        assert_eq!((sea.len(), sea[0].freq, sea[0].declination), (1, 11680, 19.0));
+        assert_eq!(navaids.iter().filter(|vor| vor.id == "BRA").count(), 3);
+        assert!(navaids.iter().any(|vor| vor.id == "XYZ"));
+        assert_eq!(navaids.iter().filter(|vor| vor.id == "PDT").count(), 2);
+        assert_eq!(report.sources.last().unwrap(), "earth_nav.dat (8 navaids, 4 replacing listed stations)");
+
+        // Importing something that isn't navaid data leaves nothing half-loaded.
+        assert!(import_earth_nav(&directory, &directory.join("school.txt")).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn built_in_declinations_match_the_published_slaved_variation() {
+        let built_in = parse_vor_list(BUILT_IN_VORS, "built-in", &mut ValidationReport::default());
+        let mut shared = 0;
+        for navaid in parse_earth_nav(EARTH_NAV_1200).unwrap().iter().filter(|navaid| navaid.kind != NavaidKind::Ndb) {
+            if let Some((_, station)) = built_in.iter().find(|(_, vor)| vor.is_station(&navaid.id, navaid.lat, navaid.lon, false)) {
+                assert_eq!(station.declination, navaid.declination, "{}", station.id);
+                shared += 1;
+            }
+        }
+        assert_eq!(shared, 4);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::calculations::calculate_distance;
+use crate::data::VorInfo;
+
+// Grid cells of one degree of latitude and longitude.
+const CELL_DEGREES: f64 = 1.0;
+// Half the Earth's circumference: every navaid is within this distance.
+const MAX_DISTANCE_NM: f64 = 10_800.0;
+// Radius of the first search when looking for the nearest navaids.
+const FIRST_SEARCH_NM: f64 = 50.0;
+
+// The navaid database with a lat/lon grid and a frequency table on top, so lookups
+// around the aircraft don't have to walk the whole earth_nav.dat list.
+#[derive(Debug, Default)]
+pub struct NavaidIndex {
+    navaids: Vec<VorInfo>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    by_frequency: HashMap<i32, Vec<usize>>,
+    by_ident: HashMap<String, Vec<usize>>,
+}
+
+impl NavaidIndex {
+    pub fn new(navaids: Vec<VorInfo>) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        let mut by_frequency: HashMap<i32, Vec<usize>> = HashMap::new();
+        let mut by_ident: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, navaid) in navaids.iter().enumerate() {
+            cells.entry(cell(navaid.lat, navaid.lon)).or_default().push(index);
+            by_frequency.entry(navaid.freq).or_default().push(index);
+            by_ident.entry(navaid.id.clone()).or_default().push(index);
+        }
+        Self { navaids, cells, by_frequency, by_ident }
+    }
+
+    pub fn navaids(&self) -> &[VorInfo] {
+        &self.navaids
+    }
+
+    // Navaids no further than `radius_nm` from lat/lon, nearest first.
+    pub fn within_radius(&self, lat: f64, lon: f64, radius_nm: f64) -> Vec<(&VorInfo, f64)> {
+        let lat_span = radius_nm / 60.0;
+        let lat_min = (lat - lat_span).max(-90.0);
+        let lat_max = (lat + lat_span).min(90.0);
+        // Meridians converge, so the widest part of the circle is at the latitude nearest the pole.
+        let widest = lat_min.abs().max(lat_max.abs());
+        let lon_span = if widest < 89.0 { lat_span / widest.to_radians().cos() } else { 180.0 };
+
+        let rows = (lat_min / CELL_DEGREES).floor() as i32..=(lat_max / CELL_DEGREES).floor() as i32;
+        let columns: Vec<i32> = if lon_span >= 179.0 {
+            (-180..180).collect()
+        } else {
+            let first = ((lon - lon_span) / CELL_DEGREES).floor() as i32;
+            let last = ((lon + lon_span) / CELL_DEGREES).floor() as i32;
+            (first..=last).map(wrap_column).collect()
+        };
+
+        let mut found: Vec<(&VorInfo, f64)> = rows
+            .flat_map(|row| columns.iter().map(move |&column| (row, column)))
+            .filter_map(|key| self.cells.get(&key))
+            .flatten()
+            .map(|&index| {
+                let navaid = &self.navaids[index];
+                (navaid, calculate_distance(lat, lon, navaid.lat, navaid.lon))
+            })
+            .filter(|(_, distance)| *distance <= radius_nm)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    // The `count` navaids closest to lat/lon, nearest first. The search circle grows
+    // until it holds enough of them.
+    pub fn nearest(&self, lat: f64, lon: f64, count: usize) -> Vec<(&VorInfo, f64)> {
+        let mut radius = FIRST_SEARCH_NM;
+        loop {
+            let mut found = self.within_radius(lat, lon, radius);
+            if found.len() >= count || radius >= MAX_DISTANCE_NM {
+                found.truncate(count);
+                return found;
+            }
+            radius *= 2.0;
+        }
+    }
+
+    // Position in `navaids()` of the station `id` near lat/lon (see VorInfo::is_station).
+    pub fn find_station(&self, id: &str, lat: f64, lon: f64, ndb: bool) -> Option<usize> {
+        self.by_ident.get(id)?.iter().copied().find(|&index| self.navaids[index].is_station(id, lat, lon, ndb))
+    }
+
+    pub fn on_frequency(&self, freq: i32) -> impl Iterator<Item = &VorInfo> {
+        self.by_frequency.get(&freq).into_iter().flatten().map(|&index| &self.navaids[index])
+    }
+
+    // The closest navaid on `freq` within `radius_nm` that `accept` agrees with.
+    pub fn nearest_on_frequency(&self, lat: f64, lon: f64, freq: i32, radius_nm: f64, accept: impl Fn(&VorInfo) -> bool) -> Option<&VorInfo> {
+        self.on_frequency(freq)
+            .filter(|navaid| accept(navaid))
+            .map(|navaid| (navaid, calculate_distance(lat, lon, navaid.lat, navaid.lon)))
+            .filter(|(_, distance)| *distance <= radius_nm)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(navaid, _)| navaid)
+    }
+}
+
+fn cell(lat: f64, lon: f64) -> (i32, i32) {
+    ((lat / CELL_DEGREES).floor() as i32, wrap_column((lon / CELL_DEGREES).floor() as i32))
+}
+
+// Columns run from -180 to 179, so a search across the antimeridian wraps around.
+fn wrap_column(column: i32) -> i32 {
+    (column + 180).rem_euclid(360) - 180
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::NavaidKind;
+
+    #[test]
+    fn finds_the_same_navaids_as_a_full_scan() {
+        let navaid = |id: &str, lat: f64, lon: f64, freq: i32| VorInfo {
+            country: String::new(),
+            id: id.to_string(),
+            name: String::new(),
+            lat,
+            lon,
+            freq,
+            declination: 0.0,
+            kind: NavaidKind::Vor,
+        };
+        let index = NavaidIndex::new(vec![
+            navaid("BRA", 40.469, -3.558, 11645),
+            navaid("CJN", 40.371, -3.785, 11530),
+            navaid("TLD", 39.972, -4.339, 11390),
+            navaid("NAN", -17.755, 177.447, 11230),
+            navaid("FUA", -14.3, -178.1, 11645),
+            navaid("ALE", 82.5, -62.3, 11330),
+            navaid("THU", 76.5, 68.8, 11330),
+        ]);
+
+        let ids = |found: Vec<(&VorInfo, f64)>| found.iter().map(|(navaid, _)| navaid.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(index.within_radius(40.4, -3.7, 60.0)), ["CJN", "BRA", "TLD"]);
+        // Across the antimeridian and over the pole.
+        assert_eq!(ids(index.within_radius(-16.0, 179.9, 300.0)), ["FUA", "NAN"]);
+        assert_eq!(ids(index.within_radius(89.5, 0.0, 900.0)), ["ALE", "THU"]);
+
+        for (lat, lon) in [(40.0, -3.0), (-16.0, -179.5), (85.0, 10.0), (0.0, 0.0)] {
+            let mut full_scan: Vec<(&VorInfo, f64)> =
+                index.navaids().iter().map(|navaid| (navaid, calculate_distance(lat, lon, navaid.lat, navaid.lon))).collect();
+            full_scan.sort_by(|a, b| a.1.total_cmp(&b.1));
+            full_scan.truncate(3);
+            assert_eq!(ids(index.nearest(lat, lon, 3)), ids(full_scan));
+        }
+
+        let brava = index.nearest_on_frequency(40.0, -3.0, 11645, 300.0, |_| true).unwrap();
+        assert_eq!(brava.id, "BRA");
+        assert!(index.nearest_on_frequency(40.0, -3.0, 11230, 300.0, |_| true).is_none());
+        assert_eq!(index.on_frequency(11330).count(), 2);
+    }
+}
@@ -0,0 +1,457 @@
+use crate::analysis::find_fix_passage;
+use crate::calculations::{angle_difference, calculate_bearing, calculate_distance};
+use crate::data::{HoldingPattern, HoldingSide, LegLength, TrackPoint};
+use crate::geometry::{build_racetrack, from_hold_frame, hold_frame, turn_radius_nm, RacetrackParams};
+
+const MAX_SCORE: f64 = 100.0;
+const BANK_TOLERANCE_DEG: f64 = 5.0;
+const INBOUND_TRACK_WINDOW_DEG: f64 = 30.0;
+const INBOUND_CROSS_WINDOW_NM: f64 = 1.0;
+const OUTBOUND_LIMIT_TOLERANCE_NM: f64 = 0.2;
+
+// Simplified rectangular buffer around the nominal racetrack: fixed margins beyond the
+// fix, beyond the outbound end, on the holding side and on the non-holding (entry) side.
+// It is not the ICAO Doc 8168 / FAA 7130.3 protected area (no entry or wind allowances,
+// no speed or altitude scaling); it only flags a track that strays well off the racetrack.
+#[derive(Debug, Clone, Copy)]
+pub struct HoldingBuffer {
+    pub beyond_fix_nm: f64,
+    pub beyond_outbound_nm: f64,
+    pub holding_side_nm: f64,
+    pub non_holding_side_nm: f64,
+}
+
+impl Default for HoldingBuffer {
+    fn default() -> Self {
+        Self {
+            beyond_fix_nm: 2.0,
+            beyond_outbound_nm: 2.0,
+            holding_side_nm: 2.0,
+            non_holding_side_nm: 3.0,
+        }
+    }
+}
+
+impl HoldingBuffer {
+    // (along_min, along_max, cross_min, cross_max) in the hold frame.
+    fn bounds(&self, params: &RacetrackParams) -> (f64, f64, f64, f64) {
+        let racetrack = build_racetrack(params);
+        let radius = racetrack.turn_radius;
+        (
+            -(racetrack.leg_length + radius + self.beyond_outbound_nm),
+            radius + self.beyond_fix_nm,
+            -self.non_holding_side_nm,
+            2.0 * radius + self.holding_side_nm,
+        )
+    }
+
+    pub fn contains(&self, params: &RacetrackParams, lat: f64, lon: f64) -> bool {
+        let (along_min, along_max, cross_min, cross_max) = self.bounds(params);
+        let (along, cross) = hold_frame(lat, lon, params.fix_lat, params.fix_lon, params.inbound_course, params.right_turns);
+        along >= along_min && along <= along_max && cross >= cross_min && cross <= cross_max
+    }
+
+    pub fn polygon(&self, params: &RacetrackParams) -> Vec<(f64, f64)> {
+        let (along_min, along_max, cross_min, cross_max) = self.bounds(params);
+        [
+            (along_max, cross_min),
+            (along_max, cross_max),
+            (along_min, cross_max),
+            (along_min, cross_min),
+        ]
+        .iter()
+        .map(|&(along, cross)| from_hold_frame(along, cross, params.fix_lat, params.fix_lon, params.inbound_course, params.right_turns))
+        .collect()
+    }
+}
+
+pub fn holding_speed_limit(altitude: f64) -> f64 {
+    if altitude <= 6000.0 {
+        200.0
+    } else if altitude <= 14000.0 {
+        230.0
+    } else {
+        265.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreItem {
+    pub label: &'static str,
+    pub measured: String,
+    pub penalty: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct HoldingScore {
+    pub total: f64,
+    pub items: Vec<ScoreItem>,
+}
+
+pub fn score_holding(holding: &HoldingPattern, buffer: &HoldingBuffer) -> Option<HoldingScore> {
+    let (fix_lat, fix_lon) = (holding.fix_lat, holding.fix_lon);
+    let passage = find_fix_passage(&holding.track_points, fix_lat, fix_lon)?;
+    let points = &holding.track_points[passage..];
+    if points.len() < 2 {
+        return None;
+    }
+
+    let params = RacetrackParams::from_holding(holding);
+    let mut items = Vec::new();
+
+    let outside_time: f64 = points
+        .windows(2)
+        .filter(|pair| !buffer.contains(&params, pair[1].lat, pair[1].lon))
+        .map(|pair| pair[1].time - pair[0].time)
+        .sum();
+    items.push(ScoreItem {
+        label: "Holding buffer",
+        measured: format!("{:.0}s outside", outside_time),
+        penalty: (outside_time * 2.0).min(40.0),
+    });
+
+    let inbound_error = inbound_tracking_error(points, holding, fix_lat, fix_lon);
+    items.push(ScoreItem {
+        label: "Inbound tracking",
+        measured: match inbound_error {
+            Some(error) => format!("{:.1}° mean", error),
+            None => "no inbound leg yet".to_string(),
+        },
+        penalty: inbound_error.map_or(0.0, |error| ((error - 1.0).max(0.0) * 2.0).min(20.0)),
+    });
+
+    match holding.leg {
+        LegLength::Time(_) => {
+            let timing_error = inbound_timing_error(points, holding, fix_lat, fix_lon);
+            items.push(ScoreItem {
+                label: "Leg timing",
+                measured: match timing_error {
+                    Some(error) => format!("{:.0}s mean error", error),
+                    None => "no complete inbound leg".to_string(),
+                },
+                penalty: timing_error.map_or(0.0, |error| (error - 5.0).clamp(0.0, 15.0)),
+            });
+        }
+        LegLength::Distance(_) => {
+            let limit_errors = outbound_limit_errors(points, holding, &params);
+            let limit_text = match params.dme_limit {
+                Some(limit) => format!("{:.1} DME", limit.distance),
+                None => format!("{:.1} NM leg", params.leg_length_nm()),
+            };
+            let (measured, penalty) = if limit_errors.is_empty() {
+                (format!("no complete outbound leg ({})", limit_text), 0.0)
+            } else {
+                let mean = limit_errors.iter().map(|error| error.abs()).sum::<f64>() / limit_errors.len() as f64;
+                let overshoot = limit_errors.iter().cloned().fold(0.0, f64::max);
+                (
+                    format!("{:.1} NM mean, {:.1} NM past {}", mean, overshoot, limit_text),
+                    // Overshooting the limit also takes the track away from the hold, so it counts twice.
+                    (((mean - OUTBOUND_LIMIT_TOLERANCE_NM).max(0.0) + (overshoot - OUTBOUND_LIMIT_TOLERANCE_NM).max(0.0)) * 10.0).min(15.0),
+                )
+            };
+            items.push(ScoreItem {
+                label: "Outbound limit",
+                measured,
+                penalty,
+            });
+        }
+    }
+
+    let (max_bank, over_bank_time) = bank_excursions(points, holding.bank_angle + BANK_TOLERANCE_DEG);
+    items.push(ScoreItem {
+        label: "Bank angle",
+        measured: format!("max {:.0}°, {:.0}s over", max_bank, over_bank_time),
+        penalty: over_bank_time.min(10.0),
+    });
+
+    let (max_speed, overspeed_time) = overspeed(points, holding);
+    items.push(ScoreItem {
+        label: "Holding speed",
+        measured: format!("max {:.0} KIAS, {:.0}s over", max_speed, overspeed_time),
+        penalty: overspeed_time.min(15.0),
+    });
+
+    let total = (MAX_SCORE - items.iter().map(|item| item.penalty).sum::<f64>()).max(0.0);
+    Some(HoldingScore { total, items })
+}
+
+fn is_on_inbound(previous: &TrackPoint, point: &TrackPoint, holding: &HoldingPattern, fix_lat: f64, fix_lon: f64) -> Option<(f64, f64)> {
+    if calculate_distance(previous.lat, previous.lon, point.lat, point.lon) < 0.01 {
+        return None;
+    }
+    let track = calculate_bearing(previous.lat, previous.lon, point.lat, point.lon);
+    let (along, cross) = hold_frame(point.lat, point.lon, fix_lat, fix_lon, holding.inbound_course, holding.right_turns);
+    let on_inbound = along < -0.2
+        && cross.abs() <= INBOUND_CROSS_WINDOW_NM
+        && angle_difference(track, holding.inbound_course).abs() <= INBOUND_TRACK_WINDOW_DEG;
+    if on_inbound { Some((along, cross)) } else { None }
+}
+
+fn inbound_tracking_error(points: &[TrackPoint], holding: &HoldingPattern, fix_lat: f64, fix_lon: f64) -> Option<f64> {
+    let errors: Vec<f64> = points
+        .windows(2)
+        .filter_map(|pair| is_on_inbound(&pair[0], &pair[1], holding, fix_lat, fix_lon))
+        .map(|(along, cross)| cross.atan2(-along).to_degrees().abs())
+        .collect();
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.iter().sum::<f64>() / errors.len() as f64)
+    }
+}
+
+// Compares each inbound leg that ends at the fix against the nominal leg duration.
+fn inbound_timing_error(points: &[TrackPoint], holding: &HoldingPattern, fix_lat: f64, fix_lon: f64) -> Option<f64> {
+    let expected = match holding.leg {
+        LegLength::Time(seconds) => seconds,
+        LegLength::Distance(_) => return None,
+    };
+
+    let mut errors = Vec::new();
+    let mut leg_start: Option<f64> = None;
+
+    for pair in points.windows(2) {
+        match is_on_inbound(&pair[0], &pair[1], holding, fix_lat, fix_lon) {
+            Some(_) => {
+                leg_start.get_or_insert(pair[0].time);
+            }
+            None => {
+                if let Some(start) = leg_start.take() {
+                    let (along, _) = hold_frame(pair[1].lat, pair[1].lon, fix_lat, fix_lon, holding.inbound_course, holding.right_turns);
+                    if along > -0.5 {
+                        errors.push((pair[0].time - start - expected).abs());
+                    }
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.iter().sum::<f64>() / errors.len() as f64)
+    }
+}
+
+// Signed distance past the outbound limit at each outbound turn (negative when
+// turning early): slant range to the DME station for DME holds, along-track
+// distance otherwise.
+fn outbound_limit_errors(points: &[TrackPoint], holding: &HoldingPattern, params: &RacetrackParams) -> Vec<f64> {
+    let radius = turn_radius_nm(params.true_airspeed, params.bank_angle);
+    let leg_length = params.leg_length_nm();
+    let mut errors = Vec::new();
+    let mut turn_point: Option<&TrackPoint> = None;
+
+    for pair in points.windows(2) {
+        let (previous, point) = (&pair[0], &pair[1]);
+        if calculate_distance(previous.lat, previous.lon, point.lat, point.lon) < 0.01 {
+            continue;
+        }
+        let track = calculate_bearing(previous.lat, previous.lon, point.lat, point.lon);
+        let (along, cross) = hold_frame(point.lat, point.lon, params.fix_lat, params.fix_lon, params.inbound_course, params.right_turns);
+        let on_outbound = along < 0.0
+            && cross > radius
+            && angle_difference(track, holding.outbound_course).abs() <= INBOUND_TRACK_WINDOW_DEG;
+
+        if on_outbound {
+            turn_point = Some(point);
+        } else if let Some(end) = turn_point.take() {
+            let error = match params.dme_limit {
+                Some(limit) => {
+                    let range = calculate_distance(end.lat, end.lon, limit.station_lat, limit.station_lon);
+                    match limit.side {
+                        HoldingSide::AwayFromStation => range - limit.distance,
+                        HoldingSide::TowardStation => limit.distance - range,
+                    }
+                }
+                None => {
+                    let (end_along, _) = hold_frame(end.lat, end.lon, params.fix_lat, params.fix_lon, params.inbound_course, params.right_turns);
+                    -end_along - leg_length
+                }
+            };
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+// Uses the reported roll where the source gives one; otherwise bank is estimated
+// from heading rate: tan φ = ω·V / g.
+fn bank_excursions(points: &[TrackPoint], bank_limit: f64) -> (f64, f64) {
+    let mut max_bank: f64 = 0.0;
+    let mut over_time = 0.0;
+
+    for pair in points.windows(2) {
+        let dt = pair[1].time - pair[0].time;
+        if dt <= 0.0 {
+            continue;
+        }
+        let bank = match pair[1].bank {
+            Some(bank) => bank.abs(),
+            None => {
+                let rate = angle_difference(pair[1].heading, pair[0].heading).to_radians() / dt;
+                let speed_ms = pair[1].groundspeed * 0.514444;
+                (rate.abs() * speed_ms / 9.80665).atan().to_degrees()
+            }
+        };
+
+        max_bank = max_bank.max(bank);
+        if bank > bank_limit {
+            over_time += dt;
+        }
+    }
+    (max_bank, over_time)
+}
+
+// Uses the reported IAS when the sample has it. Older recordings and sources without it
+// fall back to the wind-corrected TAS, converted at 2% per 1000 ft.
+fn overspeed(points: &[TrackPoint], holding: &HoldingPattern) -> (f64, f64) {
+    let mut max_speed: f64 = 0.0;
+    let mut over_time = 0.0;
+
+    for pair in points.windows(2) {
+        let dt = pair[1].time - pair[0].time;
+        if dt <= 0.0 {
+            continue;
+        }
+        let point = &pair[1];
+        let indicated = point.indicated_airspeed.unwrap_or_else(|| {
+            let wind = holding.wind.wind_at(point.alt);
+            let track = calculate_bearing(pair[0].lat, pair[0].lon, point.lat, point.lon).to_radians();
+            let wind_to = (wind.direction + 180.0).to_radians();
+            let air_east = point.groundspeed * track.sin() - wind.speed * wind_to.sin();
+            let air_north = point.groundspeed * track.cos() - wind.speed * wind_to.cos();
+            let true_airspeed = (air_east * air_east + air_north * air_north).sqrt();
+            true_airspeed / (1.0 + 0.02 * point.alt.max(0.0) / 1000.0)
+        });
+
+        max_speed = max_speed.max(indicated);
+        if indicated > holding_speed_limit(point.alt) {
+            over_time += dt;
+        }
+    }
+    (max_speed, over_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculations::destination_point;
+
+    const FIX: (f64, f64) = (40.0, -3.0);
+    const SPEED: f64 = 180.0;
+    const STEP_S: f64 = 2.0;
+
+    fn hold() -> HoldingPattern {
+        HoldingPattern {
+            fix_lat: FIX.0,
+            fix_lon: FIX.1,
+            inbound_course: 90.0,
+            outbound_course: 270.0,
+            right_turns: true,
+            true_airspeed: SPEED,
+            leg: LegLength::Time(60.0),
+            ..Default::default()
+        }
+    }
+
+    // Two laps of the racetrack for `leg`, joined on the inbound course 3 NM out and
+    // sampled every two seconds in still air. `bank` is reported while turning.
+    fn flown_track(leg: LegLength, bank: Option<f64>) -> Vec<TrackPoint> {
+        let params = RacetrackParams { leg, ..RacetrackParams::from_holding(&hold()) };
+        let racetrack = build_racetrack(&params);
+        let mut path = vec![destination_point(FIX.0, FIX.1, 270.0, 3.0)];
+        path.extend(&racetrack.points);
+        path.extend(&racetrack.points);
+
+        let step_nm = SPEED * STEP_S / 3600.0;
+        let mut points: Vec<TrackPoint> = Vec::new();
+        let mut carried = step_nm;
+        for pair in path.windows(2) {
+            let ((lat, lon), (next_lat, next_lon)) = (pair[0], pair[1]);
+            let length = calculate_distance(lat, lon, next_lat, next_lon);
+            let heading = calculate_bearing(lat, lon, next_lat, next_lon);
+            let mut along = step_nm - carried;
+            while along <= length {
+                let (lat, lon) = destination_point(lat, lon, heading, along);
+                let time = points.len() as f64 * STEP_S;
+                points.push(TrackPoint { lat, lon, time, alt: 8000.0, heading, groundspeed: SPEED, bank: None, indicated_airspeed: None });
+                along += step_nm;
+            }
+            carried = length - (along - step_nm);
+        }
+        // Heading along the chords, so a turn shows a steady rate.
+        for index in 1..points.len() {
+            let (previous, point) = (&points[index - 1], &points[index]);
+            points[index].heading = calculate_bearing(previous.lat, previous.lon, point.lat, point.lon);
+        }
+
+        if let Some(bank) = bank {
+            for index in 1..points.len() {
+                let turning = angle_difference(points[index].heading, points[index - 1].heading).abs() > 1.0;
+                points[index].bank = Some(if turning { bank } else { 0.0 });
+            }
+        }
+        points
+    }
+
+    fn item<'a>(score: &'a HoldingScore, label: &str) -> &'a ScoreItem {
+        score.items.iter().find(|item| item.label == label).unwrap()
+    }
+
+    #[test]
+    fn a_nominal_hold_keeps_almost_every_point() {
+        for bank in [None, Some(25.0)] {
+            let holding = HoldingPattern { track_points: flown_track(LegLength::Time(60.0), bank), ..hold() };
+            let score = score_holding(&holding, &HoldingBuffer::default()).unwrap();
+
+            assert_eq!(item(&score, "Holding buffer").penalty, 0.0);
+            assert_eq!(item(&score, "Inbound tracking").penalty, 0.0);
+            assert_eq!(item(&score, "Bank angle").penalty, 0.0, "{:?}", item(&score, "Bank angle"));
+            assert_eq!(item(&score, "Holding speed").penalty, 0.0);
+            assert!(score.total >= 95.0, "{:?}", score);
+        }
+    }
+
+    #[test]
+    fn penalises_leaving_the_holding_buffer_and_over_banking() {
+        // Outbound for three minutes instead of one: 6 NM past the nominal turn.
+        let track = flown_track(LegLength::Time(180.0), Some(35.0));
+        let buffer = HoldingBuffer::default();
+        let params = RacetrackParams::from_holding(&hold());
+        assert!(track.iter().any(|point| !buffer.contains(&params, point.lat, point.lon)));
+
+        let holding = HoldingPattern { track_points: track, ..hold() };
+        let score = score_holding(&holding, &buffer).unwrap();
+        assert!(item(&score, "Holding buffer").penalty >= 40.0);
+        assert!(item(&score, "Leg timing").penalty > 0.0);
+        // The reported roll counts, even though the track was flown at the 25° radius.
+        assert!(item(&score, "Bank angle").penalty > 0.0);
+        assert!(item(&score, "Bank angle").measured.starts_with("max 35°"));
+        let penalties: f64 = score.items.iter().map(|item| item.penalty).sum();
+        assert_eq!(score.total, MAX_SCORE - penalties);
+        assert!(score.total <= 50.0);
+    }
+
+    #[test]
+    fn scores_holding_speed_on_the_reported_ias() {
+        // 230 kt over the ground at 5000 ft: over the 200 KIAS limit in calm air, but a
+        // tailwind the trainer doesn't know about can explain it.
+        let mut track = flown_track(LegLength::Time(60.0), None);
+        for point in &mut track {
+            point.alt = 5000.0;
+            point.groundspeed = 230.0;
+        }
+        let estimated = HoldingPattern { track_points: track.clone(), ..hold() };
+        let score = score_holding(&estimated, &HoldingBuffer::default()).unwrap();
+        assert!(item(&score, "Holding speed").penalty > 0.0);
+
+        for point in &mut track {
+            point.indicated_airspeed = Some(185.0);
+        }
+        let reported = HoldingPattern { track_points: track, ..hold() };
+        let score = score_holding(&reported, &HoldingBuffer::default()).unwrap();
+        assert_eq!(item(&score, "Holding speed").penalty, 0.0);
+        assert_eq!(item(&score, "Holding speed").measured, "max 185 KIAS, 0s over");
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Instant;
+
+pub const TIME_RATES: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+
+// Seconds since the clock started, advanced from wall time at `rate` unless paused.
+// Track points are stamped with this time so they can be replayed and rewound.
+#[derive(Debug, Clone)]
+pub struct SimClock {
+    elapsed: f64,
+    last_tick: Option<Instant>,
+    max_step: f64,
+    pub rate: f64,
+    pub paused: bool,
+}
+
+impl SimClock {
+    // Long wall-clock gaps (window dragged, tab hidden) are clamped to `max_step`
+    // so the simulation doesn't jump ahead.
+    pub fn new(max_step: f64) -> Self {
+        Self {
+            elapsed: 0.0,
+            last_tick: None,
+            max_step,
+            rate: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn realtime() -> Self {
+        Self::new(f64::INFINITY)
+    }
+
+    pub fn tick(&mut self) -> f64 {
+        let now = Instant::now();
+        let wall_dt = self.last_tick.map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_tick = Some(now);
+        self.advance(wall_dt)
+    }
+
+    // Simulated seconds that pass in `wall_dt` seconds of wall time.
+    fn advance(&mut self, wall_dt: f64) -> f64 {
+        if self.paused {
+            return 0.0;
+        }
+        let dt = wall_dt.min(self.max_step) * self.rate;
+        self.elapsed += dt;
+        dt
+    }
+
+    pub fn now(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn rewind_to(&mut self, time: f64) {
+        self.elapsed = time.min(self.elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_pauses_and_rewinds_simulated_time() {
+        let mut clock = SimClock::new(0.5);
+        assert_eq!(clock.advance(0.2), 0.2);
+
+        clock.rate = 4.0;
+        assert_eq!(clock.advance(0.25), 1.0);
+        // A long stall only counts as one maximum step.
+        assert_eq!(clock.advance(10.0), 2.0);
+        assert_eq!(clock.now(), 3.2);
+
+        clock.paused = true;
+        assert_eq!(clock.advance(0.25), 0.0);
+        assert_eq!(clock.now(), 3.2);
+
+        clock.rewind_to(1.5);
+        assert_eq!(clock.now(), 1.5);
+        // Rewinding never moves the clock forward.
+        clock.rewind_to(9.0);
+        assert_eq!(clock.now(), 1.5);
+        clock.paused = false;
+        clock.rate = 1.0;
+        clock.advance(0.5);
+        assert_eq!(clock.now(), 2.0);
+    }
+}
@@ -0,0 +1,137 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use super::health::STALE_AFTER;
+use super::{Capabilities, SourceStatus, TelemetrySource};
+use crate::data::XPlaneData;
+
+pub const BRIDGE_PATH_VAR: &str = "HOLDING_TRAINER_BRIDGE";
+const BRIDGE_FILE_NAME: &str = "xplane_data.json";
+
+// Must resolve to the same file as bridge_file_path() in holding_practice.lua:
+// the override variable, else the per-user temp (Windows) or runtime directory.
+pub fn default_bridge_path() -> PathBuf {
+    if let Some(path) = non_empty_var(BRIDGE_PATH_VAR) {
+        return PathBuf::from(path);
+    }
+
+    let directory = if cfg!(windows) {
+        non_empty_var("TEMP")
+            .or_else(|| non_empty_var("TMP"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                let appdata = non_empty_var("LOCALAPPDATA")
+                    .or_else(|| non_empty_var("USERPROFILE"))
+                    .unwrap_or_else(|| String::from("C:\\"));
+                PathBuf::from(appdata).join("Temp")
+            })
+    } else {
+        non_empty_var("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+    };
+    directory.join(BRIDGE_FILE_NAME)
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Reads the JSON file that holding_practice.lua rewrites every second from X-Plane.
+pub struct FileBridgeSource {
+    path: PathBuf,
+    status: SourceStatus,
+    last_read: Option<Instant>,
+}
+
+impl FileBridgeSource {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            status: SourceStatus::Disconnected,
+            last_read: None,
+        }
+    }
+
+    // On Windows the script has to delete the file before renaming the new one into
+    // place, so a read can find it missing, locked or (from older scripts) half written.
+    // Shortly after a good sample that is a gap, not a lost link: the last sample stays
+    // and LinkHealth reports the link as stale if the gap lasts.
+    fn missed(&mut self, status: SourceStatus) -> io::Result<Option<XPlaneData>> {
+        if self.last_read.is_none_or(|time| time.elapsed() > STALE_AFTER) {
+            self.status = status;
+        }
+        Ok(None)
+    }
+}
+
+impl TelemetrySource for FileBridgeSource {
+    // Nothing to open: the script may start (and create the file) at any time.
+    fn connect(&mut self) -> io::Result<()> {
+        self.status = SourceStatus::Disconnected;
+        Ok(())
+    }
+
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return self.missed(SourceStatus::Disconnected),
+            Err(e) => return self.missed(SourceStatus::Error(e.to_string())),
+        };
+
+        match serde_json::from_str::<XPlaneData>(&content) {
+            Ok(data) => {
+                self.status = SourceStatus::Connected;
+                self.last_read = Some(Instant::now());
+                Ok(Some(data))
+            }
+            Err(e) => self.missed(SourceStatus::Error(format!("bad bridge file: {}", e))),
+        }
+    }
+
+    fn status(&self) -> SourceStatus {
+        self.status.clone()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            station_info: true,
+            nav_tuning: true,
+            update_rate_hz: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rides_out_the_gap_while_the_script_replaces_the_file() {
+        let path = std::env::temp_dir().join(format!("holding_bridge_test_{}.json", std::process::id()));
+        let mut source = FileBridgeSource::new(path.clone());
+        source.connect().unwrap();
+        assert!(source.poll().unwrap().is_none());
+        assert_eq!(source.status(), SourceStatus::Disconnected);
+        fs::write(&path, "{\"aircraft_lat\": 40.4").unwrap();
+        assert!(source.poll().unwrap().is_none());
+        assert!(matches!(source.status(), SourceStatus::Error(_)));
+
+        let data = XPlaneData { vor_id: "BRA".to_string(), aircraft_lat: 40.4, ..Default::default() };
+        fs::write(&path, serde_json::to_string(&data).unwrap()).unwrap();
+        assert_eq!(source.poll().unwrap().unwrap().vor_id, "BRA");
+
+        // Removed before the rename, then caught half written: still connected.
+        fs::remove_file(&path).unwrap();
+        assert!(source.poll().unwrap().is_none());
+        assert_eq!(source.status(), SourceStatus::Connected);
+        fs::write(&path, "{\"aircraft_lat\": 40.4").unwrap();
+        assert!(source.poll().unwrap().is_none());
+        assert_eq!(source.status(), SourceStatus::Connected);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
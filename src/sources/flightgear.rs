@@ -0,0 +1,294 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::time::Duration;
+
+use super::{Capabilities, SourceStatus, TelemetrySource};
+use crate::data::XPlaneData;
+
+pub const DEFAULT_FLIGHTGEAR_PORT: u16 = 5500;
+pub const DEFAULT_FLIGHTGEAR_TELNET: &str = "127.0.0.1:5401";
+const GENERIC_RATE_HZ: f64 = 10.0;
+const TELNET_RATE_HZ: f64 = 5.0;
+const TELNET_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Same order as the chunks in Scripts/holding_trainer.xml.
+const PROPERTIES: [&str; 14] = [
+    "/position/latitude-deg",
+    "/position/longitude-deg",
+    "/position/altitude-ft",
+    "/orientation/heading-magnetic-deg",
+    "/velocities/groundspeed-kt",
+    "/instrumentation/nav[0]/frequencies/selected-mhz",
+    "/orientation/heading-deg",
+    "/orientation/track-deg",
+    "/environment/magnetic-variation-deg",
+    "/velocities/airspeed-kt",
+    "/orientation/roll-deg",
+    "/velocities/vertical-speed-fps",
+    "/environment/wind-from-heading-deg",
+    "/environment/wind-speed-kt",
+];
+const VERTICAL_SPEED_INDEX: usize = 11;
+
+// Listens for FlightGear's generic protocol output over UDP, as configured by
+// Scripts/holding_trainer.xml: one line per packet.
+pub struct FlightGearSource {
+    port: u16,
+    socket: Option<UdpSocket>,
+    status: SourceStatus,
+}
+
+impl FlightGearSource {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            socket: None,
+            status: SourceStatus::Disconnected,
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<Option<XPlaneData>> {
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return Err(io::ErrorKind::NotConnected.into()),
+        };
+        let mut buffer = [0u8; 1024];
+        let mut latest = None;
+
+        loop {
+            match socket.recv_from(&mut buffer) {
+                Ok((len, _)) => {
+                    if let Some(data) = parse_generic_line(&String::from_utf8_lossy(&buffer[..len])) {
+                        latest = Some(data);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(latest)
+    }
+}
+
+impl TelemetrySource for FlightGearSource {
+    fn connect(&mut self) -> io::Result<()> {
+        let result = UdpSocket::bind(("0.0.0.0", self.port)).and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        });
+        match result {
+            Ok(socket) => {
+                self.socket = Some(socket);
+                self.status = SourceStatus::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.socket = None;
+                self.status = SourceStatus::Error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>> {
+        let result = self.receive();
+        if let Err(e) = &result {
+            self.status = SourceStatus::Error(e.to_string());
+        }
+        result
+    }
+
+    fn status(&self) -> SourceStatus {
+        self.status.clone()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            station_info: false,
+            nav_tuning: true,
+            update_rate_hz: GENERIC_RATE_HZ,
+        }
+    }
+}
+
+// Asks FlightGear's property server (fgfs --telnet=5401) for each property in turn.
+// Slower than the generic protocol but needs nothing installed on the FlightGear side.
+pub struct FlightGearTelnetSource {
+    address: SocketAddr,
+    connection: Option<(TcpStream, BufReader<TcpStream>)>,
+    status: SourceStatus,
+}
+
+impl FlightGearTelnetSource {
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            connection: None,
+            status: SourceStatus::Disconnected,
+        }
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let mut stream = TcpStream::connect_timeout(&self.address, TELNET_TIMEOUT)?;
+        stream.set_read_timeout(Some(TELNET_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        // Data mode answers "get" with the bare value instead of a prompt and "path = 'value'".
+        stream.write_all(b"data\r\n")?;
+        let reader = BufReader::new(stream.try_clone()?);
+        self.connection = Some((stream, reader));
+        Ok(())
+    }
+
+    fn request(&mut self) -> io::Result<Option<XPlaneData>> {
+        let (stream, reader) = match &mut self.connection {
+            Some(connection) => connection,
+            None => return Err(io::ErrorKind::NotConnected.into()),
+        };
+
+        let request: String = PROPERTIES.iter().map(|property| format!("get {}\r\n", property)).collect();
+        stream.write_all(request.as_bytes())?;
+
+        let mut values = Vec::with_capacity(PROPERTIES.len());
+        for _ in PROPERTIES {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            values.push(line.trim().to_string());
+        }
+        // The generic protocol file scales this chunk to fpm; the raw property is fps.
+        if let Ok(fps) = values[VERTICAL_SPEED_INDEX].parse::<f64>() {
+            values[VERTICAL_SPEED_INDEX] = (fps * 60.0).to_string();
+        }
+        Ok(parse_generic_line(&values.join(",")))
+    }
+}
+
+impl TelemetrySource for FlightGearTelnetSource {
+    fn connect(&mut self) -> io::Result<()> {
+        let result = self.open();
+        self.status = match &result {
+            Ok(()) => SourceStatus::Connected,
+            Err(e) => {
+                self.connection = None;
+                SourceStatus::Error(e.to_string())
+            }
+        };
+        result
+    }
+
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>> {
+        let result = self.request();
+        if let Err(e) = &result {
+            self.connection = None;
+            self.status = SourceStatus::Error(e.to_string());
+        }
+        result
+    }
+
+    fn status(&self) -> SourceStatus {
+        self.status.clone()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            station_info: false,
+            nav_tuning: true,
+            update_rate_hz: TELNET_RATE_HZ,
+        }
+    }
+}
+
+// latitude, longitude, altitude ft, magnetic heading, groundspeed kt, NAV1 MHz, then
+// optionally true heading, true track, variation, IAS kt, roll, vertical speed fpm
+// and wind from/kt. Lines from the original six-chunk protocol file still parse.
+pub fn parse_generic_line(line: &str) -> Option<XPlaneData> {
+    let values: Vec<f64> = line
+        .trim()
+        .split(',')
+        .map(|field| field.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    let (base, extra) = match values.len() {
+        6 => (&values[..], None),
+        14 => (&values[..6], Some(&values[6..])),
+        _ => return None,
+    };
+
+    let mut data = XPlaneData {
+        aircraft_lat: base[0],
+        aircraft_lon: base[1],
+        aircraft_alt: base[2],
+        aircraft_heading: base[3],
+        aircraft_groundspeed: base[4],
+        vor_freq: (base[5] * 100.0).round() as i32,
+        ..Default::default()
+    };
+    if let Some(extra) = extra {
+        data.aircraft_heading_true = extra[0];
+        data.aircraft_track = extra[1];
+        data.magnetic_variation = extra[2];
+        data.indicated_airspeed = extra[3];
+        data.bank_angle = extra[4];
+        data.vertical_speed = extra[5];
+        data.wind_direction = extra[6];
+        data.wind_speed = extra[7];
+    }
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_generic_protocol_lines() {
+        let data = parse_generic_line("37.618805,-122.375416,8000.5,284.2,175.3,115.80\n").unwrap();
+        assert!((data.aircraft_lat - 37.618805).abs() < 1e-9);
+        assert!((data.aircraft_lon + 122.375416).abs() < 1e-9);
+        assert_eq!(data.aircraft_alt, 8000.5);
+        assert_eq!(data.aircraft_heading, 284.2);
+        assert_eq!(data.aircraft_groundspeed, 175.3);
+        assert_eq!(data.vor_freq, 11580);
+
+        let data = parse_generic_line("37.6,-122.3,8000,284,175,115.8,297.5,290.1,13.5,150.2,-18.0,-600.0,250,20").unwrap();
+        assert_eq!((data.aircraft_heading_true, data.aircraft_track, data.magnetic_variation), (297.5, 290.1, 13.5));
+        assert_eq!((data.indicated_airspeed, data.bank_angle, data.vertical_speed), (150.2, -18.0, -600.0));
+        assert_eq!((data.wind_direction, data.wind_speed), (250.0, 20.0));
+
+        assert!(parse_generic_line("37.6,-122.3,8000").is_none());
+        assert!(parse_generic_line("37.6,-122.3,8000,nan?,175,115.8").is_none());
+    }
+
+    #[test]
+    fn reads_properties_over_telnet() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            assert_eq!(lines.next().unwrap().unwrap(), "data");
+            for (property, value) in PROPERTIES.iter().zip(["51.4706", "-0.4619", "7000", "85.5", "210.0", "113.60", "85.0", "88.0", "-0.5", "180.0", "0.0", "-10.0", "200", "12"]) {
+                assert_eq!(lines.next().unwrap().unwrap(), format!("get {}", property));
+                writer.write_all(format!("{}\r\n", value).as_bytes()).unwrap();
+            }
+        });
+
+        let mut source = FlightGearTelnetSource::new(address);
+        source.connect().unwrap();
+        let data = source.poll().unwrap().unwrap();
+        assert_eq!((data.aircraft_lat, data.aircraft_lon, data.aircraft_alt), (51.4706, -0.4619, 7000.0));
+        assert_eq!((data.aircraft_heading, data.aircraft_groundspeed, data.vor_freq), (85.5, 210.0, 11360));
+        assert_eq!((data.vertical_speed, data.wind_speed), (-600.0, 12.0));
+        server.join().unwrap();
+
+        // FlightGear went away: the next poll reports it so the source gets reconnected.
+        assert!(source.poll().is_err());
+        assert!(matches!(source.status(), SourceStatus::Error(_)));
+    }
+}
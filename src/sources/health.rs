@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::SourceStatus;
+use crate::data::XPlaneData;
+
+// No new position for this long and the link counts as stale (sim paused or frozen).
+pub const STALE_AFTER: Duration = Duration::from_secs(3);
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Disconnected,
+    Waiting,
+    Live,
+    Stale,
+    Error(String),
+}
+
+impl ConnectionState {
+    pub fn label(&self) -> String {
+        match self {
+            ConnectionState::Disconnected => "Disconnected".to_string(),
+            ConnectionState::Waiting => "Waiting for data".to_string(),
+            ConnectionState::Live => "Live".to_string(),
+            ConnectionState::Stale => "Stale: no new data".to_string(),
+            ConnectionState::Error(message) => format!("Error: {}", message),
+        }
+    }
+}
+
+// Tracks when the aircraft last actually moved. Paused simulators keep sending (or
+// the Lua script keeps rewriting) the same sample, so repeats don't count as updates.
+#[derive(Debug, Default)]
+pub struct LinkHealth {
+    last_sample: Option<XPlaneData>,
+    updates: VecDeque<Instant>,
+}
+
+impl LinkHealth {
+    // Returns true if the sample is new and should be tracked.
+    pub fn record(&mut self, sample: &XPlaneData, now: Instant) -> bool {
+        let repeated = self.last_sample.as_ref().is_some_and(|last| {
+            last.aircraft_lat == sample.aircraft_lat
+                && last.aircraft_lon == sample.aircraft_lon
+                && last.aircraft_alt == sample.aircraft_alt
+                && last.aircraft_heading == sample.aircraft_heading
+        });
+        if repeated {
+            return false;
+        }
+
+        self.last_sample = Some(sample.clone());
+        self.updates.push_back(now);
+        while self.updates.front().is_some_and(|&time| now.duration_since(time) > RATE_WINDOW) {
+            self.updates.pop_front();
+        }
+        true
+    }
+
+    pub fn last_update(&self) -> Option<Instant> {
+        self.updates.back().copied()
+    }
+
+    pub fn state(&self, status: &SourceStatus, now: Instant) -> ConnectionState {
+        match status {
+            SourceStatus::Error(message) => ConnectionState::Error(message.clone()),
+            SourceStatus::Disconnected => ConnectionState::Disconnected,
+            SourceStatus::Connected => match self.last_update() {
+                None => ConnectionState::Waiting,
+                Some(last) if now.duration_since(last) > STALE_AFTER => ConnectionState::Stale,
+                Some(_) => ConnectionState::Live,
+            },
+        }
+    }
+
+    // Updates per second over the last few seconds.
+    pub fn rate_hz(&self, now: Instant) -> f64 {
+        let recent: Vec<&Instant> = self.updates.iter().filter(|&&time| now.duration_since(time) <= RATE_WINDOW).collect();
+        match (recent.first(), recent.last()) {
+            (Some(&&first), Some(&&last)) if recent.len() > 1 => {
+                let span = last.duration_since(first).as_secs_f64();
+                if span > 0.0 { (recent.len() - 1) as f64 / span } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(lat: f64) -> XPlaneData {
+        XPlaneData { aircraft_lat: lat, aircraft_lon: -3.5, aircraft_alt: 8000.0, aircraft_heading: 90.0, ..Default::default() }
+    }
+
+    #[test]
+    fn goes_stale_when_the_sim_repeats_itself() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut health = LinkHealth::default();
+        let connected = SourceStatus::Connected;
+
+        assert_eq!(health.state(&SourceStatus::Disconnected, start), ConnectionState::Disconnected);
+        assert_eq!(health.state(&connected, start), ConnectionState::Waiting);
+
+        for i in 0..10 {
+            assert!(health.record(&sample(40.0 + i as f64 * 0.001), at(i * 100)));
+        }
+        assert_eq!(health.state(&connected, at(950)), ConnectionState::Live);
+        assert!((health.rate_hz(at(950)) - 10.0).abs() < 0.01);
+
+        // Paused: the same sample keeps arriving but doesn't count.
+        for i in 10..50 {
+            assert!(!health.record(&sample(40.009), at(i * 100)));
+        }
+        assert_eq!(health.state(&connected, at(5000)), ConnectionState::Stale);
+        assert_eq!(health.rate_hz(at(7000)), 0.0);
+
+        assert!(health.record(&sample(40.01), at(5100)));
+        assert_eq!(health.state(&connected, at(5100)), ConnectionState::Live);
+        assert!(matches!(health.state(&SourceStatus::Error("refused".into()), at(5100)), ConnectionState::Error(_)));
+    }
+}
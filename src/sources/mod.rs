@@ -0,0 +1,175 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::data::{NavaidKind, SourceKind, XPlaneData};
+use crate::navindex::NavaidIndex;
+
+mod file_bridge;
+mod flightgear;
+mod health;
+mod replay;
+mod xplane_udp;
+
+pub use file_bridge::{default_bridge_path, FileBridgeSource, BRIDGE_PATH_VAR};
+pub use flightgear::{FlightGearSource, FlightGearTelnetSource, DEFAULT_FLIGHTGEAR_PORT, DEFAULT_FLIGHTGEAR_TELNET};
+pub use health::{ConnectionState, LinkHealth};
+pub use replay::{recording_path, Recorder, ReplaySource};
+pub use xplane_udp::{XPlaneUdpSource, DEFAULT_XPLANE_ADDRESS};
+
+const STATION_SEARCH_RADIUS_NM: f64 = 300.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceStatus {
+    Disconnected,
+    Connected,
+    Error(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    // Reports the tuned station's ident and position; otherwise it's looked up by frequency.
+    pub station_info: bool,
+    pub nav_tuning: bool,
+    pub update_rate_hz: f64,
+}
+
+// A simulator (or recording) feeding `XPlaneData` samples to the capture pipeline.
+pub trait TelemetrySource: Send {
+    fn connect(&mut self) -> io::Result<()>;
+
+    // Returns the newest sample since the previous poll, if any.
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>>;
+
+    fn status(&self) -> SourceStatus;
+
+    fn capabilities(&self) -> Capabilities;
+}
+
+pub fn create_source(kind: &SourceKind) -> Box<dyn TelemetrySource> {
+    match kind {
+        SourceKind::FileBridge(path) => Box::new(FileBridgeSource::new(path.clone())),
+        SourceKind::XPlaneUdp(address) => Box::new(XPlaneUdpSource::new(*address)),
+        SourceKind::FlightGear(port) => Box::new(FlightGearSource::new(*port)),
+        SourceKind::FlightGearTelnet(address) => Box::new(FlightGearTelnetSource::new(*address)),
+        SourceKind::Replay(path) => Box::new(ReplaySource::new(path.clone())),
+    }
+}
+
+// Shared between the UI, which picks the source, and the polling thread, which
+// reports back how it is doing.
+#[derive(Debug, Clone)]
+pub struct LiveLink {
+    pub kind: SourceKind,
+    pub state: ConnectionState,
+    pub last_update: Option<SystemTime>,
+    pub data_rate_hz: f64,
+    pub capabilities: Option<Capabilities>,
+    pub recording: bool,
+}
+
+impl Default for LiveLink {
+    fn default() -> Self {
+        Self {
+            kind: SourceKind::FileBridge(default_bridge_path()),
+            state: ConnectionState::Disconnected,
+            last_update: None,
+            data_rate_hz: 0.0,
+            capabilities: None,
+            recording: false,
+        }
+    }
+}
+
+// What the user typed for each source, kept while another source is selected.
+#[derive(Debug, Clone)]
+pub struct SourceSettings {
+    pub bridge_path: String,
+    pub xplane_address: String,
+    pub flightgear_port: String,
+    pub flightgear_telnet: String,
+    pub replay_path: String,
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        Self {
+            bridge_path: default_bridge_path().display().to_string(),
+            xplane_address: DEFAULT_XPLANE_ADDRESS.to_string(),
+            flightgear_port: DEFAULT_FLIGHTGEAR_PORT.to_string(),
+            flightgear_telnet: DEFAULT_FLIGHTGEAR_TELNET.to_string(),
+            replay_path: recording_path().display().to_string(),
+        }
+    }
+}
+
+impl SourceSettings {
+    pub fn file_bridge(&self) -> Option<SourceKind> {
+        let path = self.bridge_path.trim();
+        (!path.is_empty()).then(|| SourceKind::FileBridge(PathBuf::from(path)))
+    }
+
+    pub fn xplane_udp(&self) -> Option<SourceKind> {
+        self.xplane_address.trim().parse().ok().map(SourceKind::XPlaneUdp)
+    }
+
+    pub fn flightgear(&self) -> Option<SourceKind> {
+        self.flightgear_port.trim().parse().ok().map(SourceKind::FlightGear)
+    }
+
+    pub fn flightgear_telnet(&self) -> Option<SourceKind> {
+        self.flightgear_telnet.trim().parse().ok().map(SourceKind::FlightGearTelnet)
+    }
+
+    pub fn replay(&self) -> Option<SourceKind> {
+        let path = self.replay_path.trim();
+        (!path.is_empty()).then(|| SourceKind::Replay(PathBuf::from(path)))
+    }
+}
+
+// The nearest VOR on the tuned NAV1 frequency and NDB on the ADF1 frequency, for
+// sources that only report tuning.
+pub fn identify_station(data: &mut XPlaneData, navaids: &NavaidIndex) {
+    let (lat, lon) = (data.aircraft_lat, data.aircraft_lon);
+    let vor = navaids.nearest_on_frequency(lat, lon, data.vor_freq, STATION_SEARCH_RADIUS_NM, |vor| vor.kind != NavaidKind::Ndb);
+    (data.vor_id, data.vor_lat, data.vor_lon) = match vor {
+        Some(vor) => (vor.id.clone(), vor.lat, vor.lon),
+        None => (String::new(), 0.0, 0.0),
+    };
+    let ndb = navaids.nearest_on_frequency(lat, lon, data.adf_freq, STATION_SEARCH_RADIUS_NM, |ndb| ndb.kind == NavaidKind::Ndb);
+    (data.adf_id, data.adf_lat, data.adf_lon) = match ndb {
+        Some(ndb) => (ndb.id.clone(), ndb.lat, ndb.lon),
+        None => (String::new(), 0.0, 0.0),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::VorInfo;
+
+    #[test]
+    fn identifies_the_nearest_station_on_the_tuned_frequency() {
+        let vor = |id: &str, lat: f64, lon: f64, freq: i32| VorInfo {
+            country: String::new(),
+            id: id.to_string(),
+            name: String::new(),
+            lat,
+            lon,
+            freq,
+            declination: 0.0,
+            kind: NavaidKind::Vor,
+        };
+        let ndb = VorInfo { kind: NavaidKind::Ndb, ..vor("PDT", 40.4, -3.7, 380) };
+        let vors = NavaidIndex::new(vec![vor("FAR", 50.0, 10.0, 11645), vor("BRA", 40.469, -3.558, 11645), vor("OTH", 40.5, -3.6, 11300), ndb]);
+
+        let mut data = XPlaneData { aircraft_lat: 40.3, aircraft_lon: -3.4, vor_freq: 11645, adf_freq: 380, ..Default::default() };
+        identify_station(&mut data, &vors);
+        assert_eq!((data.vor_id.as_str(), data.adf_id.as_str()), ("BRA", "PDT"));
+
+        data.vor_freq = 10800;
+        identify_station(&mut data, &vors);
+        assert!(data.vor_id.is_empty());
+        assert_eq!(data.vor_lat, 0.0);
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs::{self, File};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Capabilities, SourceStatus, TelemetrySource};
+use crate::data::XPlaneData;
+use crate::user_files;
+
+const RECORDING_FILE: &str = "holding_recording.jsonl";
+const REPLAY_RATE_HZ: f64 = 20.0;
+
+pub fn recording_path() -> PathBuf {
+    user_files::user_directory().join(RECORDING_FILE)
+}
+
+// One line of a recording: seconds since recording started and the sample as published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedSample {
+    time: f64,
+    data: XPlaneData,
+}
+
+pub struct Recorder {
+    writer: LineWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        Ok(Self {
+            writer: LineWriter::new(File::create(path)?),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, data: &XPlaneData) -> io::Result<()> {
+        let sample = RecordedSample {
+            time: self.started.elapsed().as_secs_f64(),
+            data: data.clone(),
+        };
+        let line = serde_json::to_string(&sample).map_err(io::Error::from)?;
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+// Plays a recording back in real time, so a flight can be reviewed (or a bug
+// reproduced) without a simulator running.
+pub struct ReplaySource {
+    path: PathBuf,
+    samples: Vec<RecordedSample>,
+    next: usize,
+    started: Option<Instant>,
+    status: SourceStatus,
+}
+
+impl ReplaySource {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            samples: Vec::new(),
+            next: 0,
+            started: None,
+            status: SourceStatus::Disconnected,
+        }
+    }
+
+    fn load(&mut self) -> io::Result<()> {
+        let content = fs::read_to_string(&self.path)?;
+        let samples: Vec<RecordedSample> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(io::Error::from)?;
+
+        if samples.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "recording is empty"));
+        }
+        self.samples = samples;
+        Ok(())
+    }
+
+    // The last sample due at `elapsed`; samples in between are skipped.
+    fn due_sample(&mut self, elapsed: f64) -> Option<XPlaneData> {
+        let mut due = None;
+        while let Some(sample) = self.samples.get(self.next) {
+            if sample.time > elapsed {
+                break;
+            }
+            due = Some(sample.data.clone());
+            self.next += 1;
+        }
+        due
+    }
+}
+
+impl TelemetrySource for ReplaySource {
+    fn connect(&mut self) -> io::Result<()> {
+        self.next = 0;
+        self.started = None;
+        match self.load() {
+            Ok(()) => {
+                self.status = SourceStatus::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.samples.clear();
+                self.status = SourceStatus::Error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>> {
+        if self.samples.is_empty() {
+            return Err(io::ErrorKind::NotConnected.into());
+        }
+        if self.next >= self.samples.len() {
+            self.status = SourceStatus::Disconnected;
+            return Ok(None);
+        }
+
+        let started = *self.started.get_or_insert_with(Instant::now);
+        // Recordings start when the first sample arrived, not at zero.
+        let elapsed = started.elapsed().as_secs_f64() + self.samples[0].time;
+        Ok(self.due_sample(elapsed))
+    }
+
+    fn status(&self) -> SourceStatus {
+        self.status.clone()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            station_info: true,
+            nav_tuning: true,
+            update_rate_hz: REPLAY_RATE_HZ,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_a_recording_at_its_own_pace() {
+        let path = std::env::temp_dir().join(format!("holding_replay_test_{}.jsonl", std::process::id()));
+        let lines: Vec<String> = [0.0, 0.01, 0.02]
+            .iter()
+            .map(|&time| {
+                let data = XPlaneData { vor_id: "BRA".to_string(), aircraft_heading: time * 1000.0, ..Default::default() };
+                serde_json::to_string(&RecordedSample { time: time + 100.0, data }).unwrap()
+            })
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let mut source = ReplaySource::new(path.clone());
+        source.connect().unwrap();
+        let first = source.poll().unwrap().unwrap();
+        assert_eq!((first.vor_id.as_str(), first.aircraft_heading), ("BRA", 0.0));
+
+        // Samples that fell due between polls are skipped in favour of the latest.
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(source.poll().unwrap().unwrap().aircraft_heading, 20.0);
+        assert!(source.poll().unwrap().is_none());
+        assert_eq!(source.status(), SourceStatus::Disconnected);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
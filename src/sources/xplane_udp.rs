@@ -0,0 +1,344 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use super::{Capabilities, SourceStatus, TelemetrySource};
+use crate::calculations::{angle_difference, normalize_angle};
+use crate::data::XPlaneData;
+
+pub const DEFAULT_XPLANE_ADDRESS: &str = "127.0.0.1:49000";
+pub const RREF_RATE_HZ: i32 = 20;
+
+const RREF_PATH_LEN: usize = 400;
+const RESUBSCRIBE_AFTER: Duration = Duration::from_secs(2);
+
+// Subscription index is the position in this list. A sample needs the first
+// REQUIRED_DATAREFS; the rest are filled in when the sim version has them.
+const DATAREFS: [&str; 14] = [
+    "sim/flightmodel/position/latitude",
+    "sim/flightmodel/position/longitude",
+    "sim/flightmodel/position/elevation",
+    "sim/cockpit2/gauges/indicators/heading_AHARS_deg_mag_pilot",
+    "sim/flightmodel/position/groundspeed",
+    "sim/cockpit2/radios/actuators/nav1_frequency_hz",
+    "sim/flightmodel/position/psi",
+    "sim/flightmodel/position/hpath",
+    "sim/flightmodel/position/indicated_airspeed",
+    "sim/flightmodel/position/phi",
+    "sim/flightmodel/position/vh_ind_fpm",
+    // Wind at the aircraft in OpenGL axes (x east, z south), m/s towards where it blows.
+    "sim/weather/wind_now_x_msc",
+    "sim/weather/wind_now_z_msc",
+    "sim/cockpit2/radios/actuators/adf1_frequency_hz",
+];
+const REQUIRED_DATAREFS: usize = 6;
+
+// Subscribes to datarefs with X-Plane's RREF UDP protocol. X-Plane keeps streaming
+// them to our socket at the requested rate until we unsubscribe with rate 0.
+pub struct XPlaneUdpSource {
+    target: SocketAddr,
+    socket: Option<UdpSocket>,
+    values: [Option<f32>; DATAREFS.len()],
+    last_packet: Instant,
+    last_subscribe: Instant,
+    status: SourceStatus,
+}
+
+impl XPlaneUdpSource {
+    pub fn new(target: SocketAddr) -> Self {
+        Self {
+            target,
+            socket: None,
+            values: [None; DATAREFS.len()],
+            last_packet: Instant::now(),
+            last_subscribe: Instant::now(),
+            status: SourceStatus::Disconnected,
+        }
+    }
+
+    fn subscribe(&self, rate: i32) -> io::Result<()> {
+        if let Some(socket) = &self.socket {
+            for (index, dataref) in DATAREFS.iter().enumerate() {
+                socket.send_to(&rref_request(rate, index as i32, dataref), self.target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let bind_address = if self.target.ip().is_loopback() { "127.0.0.1:0" } else { "0.0.0.0:0" };
+        let socket = UdpSocket::bind(bind_address)?;
+        socket.set_nonblocking(true)?;
+
+        self.socket = Some(socket);
+        self.values = [None; DATAREFS.len()];
+        self.last_packet = Instant::now();
+        self.last_subscribe = Instant::now();
+        self.subscribe(RREF_RATE_HZ)
+    }
+
+    // Drains every pending packet; returns a sample once all datarefs have arrived
+    // at least once and something new came in.
+    fn receive(&mut self) -> io::Result<Option<XPlaneData>> {
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return Err(io::ErrorKind::NotConnected.into()),
+        };
+        let mut buffer = [0u8; 2048];
+        let mut received = false;
+
+        loop {
+            match socket.recv_from(&mut buffer) {
+                Ok((len, _)) => {
+                    for (index, value) in parse_rref(&buffer[..len]) {
+                        if let Some(slot) = self.values.get_mut(index) {
+                            *slot = Some(value);
+                            received = true;
+                        }
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // Windows reports ICMP "port unreachable" from a previous send here.
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let now = Instant::now();
+        if received {
+            self.last_packet = now;
+        } else if now.duration_since(self.last_packet) > RESUBSCRIBE_AFTER
+            && now.duration_since(self.last_subscribe) > RESUBSCRIBE_AFTER
+        {
+            // X-Plane forgets subscriptions when it restarts or reloads the aircraft.
+            self.last_subscribe = now;
+            self.subscribe(RREF_RATE_HZ)?;
+        }
+
+        if !received || self.values[..REQUIRED_DATAREFS].iter().any(Option::is_none) {
+            return Ok(None);
+        }
+        let value = |index: usize| self.values[index].map_or(0.0, f64::from);
+
+        let heading_true = value(6);
+        let (wind_direction, wind_speed) = wind_from_velocity(value(11), value(12));
+        Ok(Some(XPlaneData {
+            aircraft_lat: value(0),
+            aircraft_lon: value(1),
+            aircraft_alt: value(2) * 3.28084,
+            aircraft_heading: value(3),
+            aircraft_groundspeed: value(4) * 1.94384,
+            vor_freq: value(5).round() as i32,
+            aircraft_heading_true: heading_true,
+            aircraft_track: normalize_angle(value(7)),
+            magnetic_variation: angle_difference(heading_true, value(3)),
+            indicated_airspeed: value(8),
+            bank_angle: value(9),
+            vertical_speed: value(10),
+            wind_direction,
+            wind_speed,
+            adf_freq: value(13).round() as i32,
+            ..Default::default()
+        }))
+    }
+}
+
+impl TelemetrySource for XPlaneUdpSource {
+    fn connect(&mut self) -> io::Result<()> {
+        let result = self.open();
+        self.status = match &result {
+            Ok(()) => SourceStatus::Connected,
+            Err(e) => {
+                self.socket = None;
+                SourceStatus::Error(e.to_string())
+            }
+        };
+        result
+    }
+
+    fn poll(&mut self) -> io::Result<Option<XPlaneData>> {
+        let result = self.receive();
+        if let Err(e) = &result {
+            self.status = SourceStatus::Error(e.to_string());
+        }
+        result
+    }
+
+    fn status(&self) -> SourceStatus {
+        self.status.clone()
+    }
+
+    // RREF can't carry the NAV1 or ADF1 ident strings, so stations are looked up by frequency.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            station_info: false,
+            nav_tuning: true,
+            update_rate_hz: RREF_RATE_HZ as f64,
+        }
+    }
+}
+
+impl Drop for XPlaneUdpSource {
+    fn drop(&mut self) {
+        let _ = self.subscribe(0);
+    }
+}
+
+// Direction the wind blows from (true) and knots, from its velocity in OpenGL axes.
+fn wind_from_velocity(east_ms: f64, south_ms: f64) -> (f64, f64) {
+    let speed = (east_ms * east_ms + south_ms * south_ms).sqrt() * 1.94384;
+    if speed < 1e-3 {
+        return (0.0, 0.0);
+    }
+    (normalize_angle((-east_ms).atan2(south_ms).to_degrees()), speed)
+}
+
+// "RREF\0", rate and index as little-endian i32, then the dataref path in a
+// zero-padded 400-byte field.
+pub fn rref_request(rate: i32, index: i32, dataref: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(5 + 8 + RREF_PATH_LEN);
+    packet.extend_from_slice(b"RREF\0");
+    packet.extend_from_slice(&rate.to_le_bytes());
+    packet.extend_from_slice(&index.to_le_bytes());
+
+    let mut path = [0u8; RREF_PATH_LEN];
+    let bytes = dataref.as_bytes();
+    let len = bytes.len().min(RREF_PATH_LEN - 1);
+    path[..len].copy_from_slice(&bytes[..len]);
+    packet.extend_from_slice(&path);
+    packet
+}
+
+// Replies are "RREF" plus one filler byte, followed by (i32 index, f32 value) pairs.
+pub fn parse_rref(packet: &[u8]) -> Vec<(usize, f32)> {
+    if packet.len() < 5 || &packet[..4] != b"RREF" {
+        return Vec::new();
+    }
+
+    packet[5..]
+        .chunks_exact(8)
+        .filter_map(|chunk| {
+            let index = i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            let value = f32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            usize::try_from(index).ok().map(|index| (index, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays X-Plane's part: records subscriptions and replays canned RREF packets.
+    struct StandIn {
+        socket: UdpSocket,
+    }
+
+    impl StandIn {
+        fn new() -> Self {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+            Self { socket }
+        }
+
+        fn address(&self) -> SocketAddr {
+            self.socket.local_addr().unwrap()
+        }
+
+        fn receive_request(&self) -> (SocketAddr, i32, i32, String) {
+            let mut buffer = [0u8; 1024];
+            let (len, from) = self.socket.recv_from(&mut buffer).unwrap();
+            assert_eq!(len, 413);
+            assert_eq!(&buffer[..5], b"RREF\0");
+            let rate = i32::from_le_bytes(buffer[5..9].try_into().unwrap());
+            let index = i32::from_le_bytes(buffer[9..13].try_into().unwrap());
+            let path = buffer[13..len].split(|&b| b == 0).next().unwrap();
+            (from, rate, index, String::from_utf8(path.to_vec()).unwrap())
+        }
+
+        fn replay(&self, to: SocketAddr, values: &[(i32, f32)]) {
+            let mut packet = b"RREF,".to_vec();
+            for (index, value) in values {
+                packet.extend_from_slice(&index.to_le_bytes());
+                packet.extend_from_slice(&value.to_le_bytes());
+            }
+            self.socket.send_to(&packet, to).unwrap();
+        }
+    }
+
+    fn poll_until_complete(source: &mut XPlaneUdpSource) -> XPlaneData {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if let Some(data) = source.poll().unwrap() {
+                return data;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("no complete sample from the stand-in");
+    }
+
+    #[test]
+    fn subscribes_and_fills_telemetry_from_replayed_packets() {
+        let stand_in = StandIn::new();
+        let mut source = XPlaneUdpSource::new(stand_in.address());
+        source.connect().unwrap();
+        assert_eq!(source.status(), SourceStatus::Connected);
+
+        let mut client_address = None;
+        for expected_index in 0..DATAREFS.len() as i32 {
+            let (from, rate, index, path) = stand_in.receive_request();
+            assert_eq!((rate, index), (RREF_RATE_HZ, expected_index));
+            assert_eq!(path, DATAREFS[index as usize]);
+            client_address = Some(from);
+        }
+        let client_address = client_address.unwrap();
+
+        // Split across packets the way X-Plane does when a reply gets large.
+        // The optional values go first so the sample isn't complete without them.
+        // 25 kt from 310: the air moves towards 130, east and south.
+        let (wind_x, wind_z) = (12.861 * 130f32.to_radians().sin(), -12.861 * 130f32.to_radians().cos());
+        stand_in.replay(client_address, &[(6, 270.0), (7, 265.0), (8, 165.0), (9, -20.0), (10, -500.0), (11, wind_x), (12, wind_z), (13, 362.0)]);
+        stand_in.replay(client_address, &[(0, 40.4691), (1, -3.5575), (2, 2438.4)]);
+        stand_in.replay(client_address, &[(3, 271.5), (4, 92.6), (5, 11645.0)]);
+
+        let data = poll_until_complete(&mut source);
+        assert!((data.aircraft_lat - 40.4691).abs() < 1e-4);
+        assert!((data.aircraft_lon + 3.5575).abs() < 1e-4);
+        assert!((data.aircraft_alt - 8000.0).abs() < 1.0);
+        assert!((data.aircraft_heading - 271.5).abs() < 1e-3);
+        assert!((data.aircraft_groundspeed - 180.0).abs() < 0.1);
+        assert_eq!(data.vor_freq, 11645);
+        assert!((data.magnetic_variation + 1.5).abs() < 1e-3);
+        assert_eq!((data.aircraft_track, data.indicated_airspeed, data.bank_angle), (265.0, 165.0, -20.0));
+        assert_eq!(data.vertical_speed, -500.0);
+        assert!((data.wind_direction - 310.0).abs() < 0.01 && (data.wind_speed - 25.0).abs() < 0.01);
+        assert_eq!(data.adf_freq, 362);
+
+        drop(source);
+        let (_, rate, _, _) = stand_in.receive_request();
+        assert_eq!(rate, 0);
+    }
+
+    #[test]
+    fn decodes_the_wind_from_its_velocity() {
+        let knots = |(direction, speed): (f64, f64)| ((direction * 10.0).round() / 10.0, (speed * 10.0).round() / 10.0);
+        // Blowing south is a northerly, blowing east a westerly.
+        assert_eq!(knots(wind_from_velocity(0.0, 10.0)), (0.0, 19.4));
+        assert_eq!(knots(wind_from_velocity(10.0, 0.0)), (270.0, 19.4));
+        assert_eq!(knots(wind_from_velocity(0.0, -10.0)), (180.0, 19.4));
+        assert_eq!(knots(wind_from_velocity(-5.0, -5.0)), (135.0, 13.7));
+        assert_eq!(wind_from_velocity(0.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn ignores_foreign_and_truncated_packets() {
+        assert!(parse_rref(b"DATA*\0\0\0\0").is_empty());
+        assert!(parse_rref(b"RRE").is_empty());
+
+        let mut packet = b"RREF,".to_vec();
+        packet.extend_from_slice(&2i32.to_le_bytes());
+        packet.extend_from_slice(&1.5f32.to_le_bytes());
+        packet.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(parse_rref(&packet), vec![(2, 1.5)]);
+    }
+}
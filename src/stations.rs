@@ -0,0 +1,242 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{NavaidKind, VorInfo};
+use crate::navindex::NavaidIndex;
+use crate::user_files;
+
+const STATIONS_FILE: &str = "holding_stations.json";
+const MAX_RECENTS: usize = 8;
+
+// Ident, type and position: what VorInfo::is_station compares, so a favourite saved
+// on the built-in list is still found after importing earth_nav.dat and the other way
+// round, although the files name the country differently.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationKey {
+    pub id: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub ndb: bool,
+}
+
+impl StationKey {
+    pub fn of(vor: &VorInfo) -> Self {
+        Self { id: vor.id.clone(), lat: vor.lat, lon: vor.lon, ndb: vor.kind == NavaidKind::Ndb }
+    }
+
+    pub fn matches(&self, vor: &VorInfo) -> bool {
+        vor.is_station(&self.id, self.lat, self.lon, self.ndb)
+    }
+}
+
+pub fn stations_path() -> PathBuf {
+    user_files::user_directory().join(STATIONS_FILE)
+}
+
+// Favourite and recently used stations, kept in the user directory between sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedStations {
+    #[serde(default)]
+    pub favourites: Vec<StationKey>,
+    // Most recent first.
+    #[serde(default)]
+    pub recents: Vec<StationKey>,
+}
+
+impl SavedStations {
+    // A missing or unreadable file just means nothing has been saved yet.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::from)?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, content)
+    }
+
+    pub fn is_favourite(&self, vor: &VorInfo) -> bool {
+        self.favourites.iter().any(|key| key.matches(vor))
+    }
+
+    pub fn toggle_favourite(&mut self, vor: &VorInfo) {
+        match self.favourites.iter().position(|favourite| favourite.matches(vor)) {
+            Some(index) => {
+                self.favourites.remove(index);
+            }
+            None => self.favourites.push(StationKey::of(vor)),
+        }
+    }
+
+    pub fn record_recent(&mut self, vor: &VorInfo) {
+        self.recents.retain(|recent| !recent.matches(vor));
+        self.recents.insert(0, StationKey::of(vor));
+        self.recents.truncate(MAX_RECENTS);
+    }
+}
+
+// Stations listed under `keys` that are in the current database, as indices into it.
+pub fn resolve(keys: &[StationKey], navaids: &NavaidIndex) -> Vec<usize> {
+    keys.iter().filter_map(|key| navaids.find_station(&key.id, key.lat, key.lon, key.ndb)).collect()
+}
+
+// Best matches for `query`, best first. Every word of the query has to match the
+// ident, name, country or frequency, exactly, as a prefix or with letters skipped.
+pub fn search(navaids: &[VorInfo], query: &str, limit: usize) -> Vec<usize> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<(usize, u32)> = navaids
+        .iter()
+        .enumerate()
+        .filter_map(|(index, vor)| match_score(vor, &words).map(|score| (index, score)))
+        .collect();
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| navaids[a.0].id.cmp(&navaids[b.0].id)));
+    matches.into_iter().take(limit).map(|(index, _)| index).collect()
+}
+
+fn match_score(vor: &VorInfo, words: &[String]) -> Option<u32> {
+    let id = vor.id.to_lowercase();
+    let name = vor.name.to_lowercase();
+    let country = vor.country.to_lowercase();
+    words.iter().map(|word| word_score(word, &id, &name, &country, vor.freq)).sum()
+}
+
+fn word_score(word: &str, id: &str, name: &str, country: &str, freq: i32) -> Option<u32> {
+    let ident = if id == word {
+        100
+    } else if id.starts_with(word) {
+        60
+    } else if is_subsequence(word, id) {
+        20
+    } else {
+        0
+    };
+    let name = if name.split_whitespace().any(|part| part.starts_with(word)) {
+        40
+    } else if name.contains(word) {
+        30
+    } else if is_subsequence(word, name) {
+        10
+    } else {
+        0
+    };
+    let country = if country.starts_with(word) { 25 } else { 0 };
+    // "116.45", "11645" and "380" all find their station; the dot is optional.
+    let digits: String = word.chars().filter(|c| *c != '.').collect();
+    let frequency = if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        0
+    } else if freq.to_string() == digits {
+        80
+    } else if freq.to_string().starts_with(&digits) {
+        30
+    } else {
+        0
+    };
+    let best = ident.max(name).max(country).max(frequency);
+    (best > 0).then_some(best)
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::NavaidKind;
+
+    fn station(country: &str, id: &str, name: &str, freq: i32) -> VorInfo {
+        VorInfo {
+            country: country.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            lat: 40.0,
+            lon: -3.0,
+            freq,
+            declination: 0.0,
+            kind: if freq < 2000 { NavaidKind::Ndb } else { NavaidKind::Vor },
+        }
+    }
+
+    #[test]
+    fn finds_stations_by_ident_name_country_and_frequency() {
+        let navaids = vec![
+            station("Spain", "BRA", "Barajas", 11645),
+            station("Spain", "BDJ", "Badajoz", 11420),
+            station("Germany", "BRAM", "Bramsche", 11300),
+            station("Spain", "PDT", "Pinto", 380),
+            station("France", "BRY", "Bray", 11490),
+        ];
+        let ids = |query: &str| search(&navaids, query, 10).into_iter().map(|index| navaids[index].id.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(ids("bra"), ["BRA", "BRAM", "BRY"]);
+        assert_eq!(ids("bdj"), ["BDJ"]);
+        assert_eq!(ids("barjs"), ["BRA"]);
+        assert_eq!(ids("br spain"), ["BRA"]);
+        assert_eq!(ids("116.45"), ["BRA"]);
+        assert_eq!(ids("380"), ["PDT"]);
+        assert!(ids("zzz").is_empty());
+        assert!(ids("  ").is_empty());
+
+        let mut saved = SavedStations::default();
+        saved.toggle_favourite(&navaids[3]);
+        for vor in [&navaids[0], &navaids[1], &navaids[0]] {
+            saved.record_recent(vor);
+        }
+        assert!(saved.is_favourite(&navaids[3]));
+        let index = NavaidIndex::new(navaids.clone());
+        assert_eq!(resolve(&saved.recents, &index), [0, 1]);
+
+        let restored: SavedStations = serde_json::from_str(&serde_json::to_string(&saved).unwrap()).unwrap();
+        assert_eq!(restored.favourites, saved.favourites);
+        saved.toggle_favourite(&navaids[3]);
+        assert!(saved.favourites.is_empty());
+    }
+
+    #[test]
+    fn resolves_favourites_across_an_earth_nav_import() {
+        use crate::navdata::{import_earth_nav, load_navaid_database};
+
+        let directory = std::env::temp_dir().join(format!("holding_stations_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let earth_nav = directory.join("earth_nav.dat");
+        std::fs::write(
+            &earth_nav,
+            "I\n1200 Version - data cycle 2101\n\
+             3  40.46913900   -3.55752800    1998 11645 130   -1.000 BRA  ENRT LE BARAJAS VOR-DME\n\
+             3  47.43537200 -122.30961700     354 11680 130   19.000 SEA  ENRT K1 SEATTLE VORTAC\n\
+             2  40.47000000   -3.56000000    2000   320  25    0.000 BRA  ENRT LE BARAJAS NDB\n\
+             99\n",
+        )
+        .unwrap();
+        let built_in = NavaidIndex::new(load_navaid_database(&directory).0);
+        let imported = NavaidIndex::new(import_earth_nav(&directory, &earth_nav).unwrap().0);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let station = |index: &NavaidIndex, id: &str| index.navaids().iter().find(|vor| vor.id == id && vor.kind != NavaidKind::Ndb).unwrap().clone();
+        let mut saved = SavedStations::default();
+        saved.toggle_favourite(&station(&built_in, "BRA"));
+        saved.toggle_favourite(&station(&built_in, "SEA"));
+
+        // Saved on "Spain" and "USA", found as "LE" and "K1" at the antenna, and not as the NDB.
+        let found: Vec<&VorInfo> = resolve(&saved.favourites, &imported).into_iter().map(|index| &imported.navaids()[index]).collect();
+        let described: Vec<(&str, &str, NavaidKind)> = found.iter().map(|vor| (vor.id.as_str(), vor.country.as_str(), vor.kind)).collect();
+        assert_eq!(described, [("BRA", "LE", NavaidKind::VorDme), ("SEA", "K1", NavaidKind::Vortac)]);
+        assert!(found.iter().all(|vor| saved.is_favourite(vor)));
+
+        // And the other way round, once the import is gone.
+        let mut saved = SavedStations::default();
+        saved.record_recent(&station(&imported, "SEA"));
+        let found = resolve(&saved.recents, &built_in);
+        assert_eq!(found.len(), 1);
+        assert_eq!(built_in.navaids()[found[0]].country, "USA");
+    }
+}
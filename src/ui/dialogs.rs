@@ -27,7 +27,7 @@ pub fn draw_about_dialog(ctx: &egui::Context, show_about: &mut bool) {
             ui.label(egui::RichText::new("Features:").strong().size(14.0));
             ui.add_space(5.0);
             ui.label("  • Simulate Mode: Practice with any VOR worldwide, no sim needed");
-            ui.label("  • X-Plane 11 Mode: Real-time practice with your favorite aircraft");
+            ui.label("  • Live Mode: Real-time practice from X-Plane, FlightGear or a recording");
             ui.label("  • Visual Sectors: See entry zones and get instant feedback");
             ui.label("  • Interactive: Adjust heading and see results immediately");
             ui.add_space(12.0);
@@ -158,6 +158,8 @@ pub fn draw_how_to_fly_dialog(ctx: &egui::Context, show_how_it_works: &mut bool)
                 ui.label("• Below 14,000' MSL: 1-minute legs");
                 ui.label("• Above 14,000' MSL: 1.5-minute legs");
                 ui.label("• Timing starts when abeam the fix outbound, or wings level, whichever occurs later");
+                ui.label("• DME holds: turn inbound at the outbound DME limit instead of timing the leg");
+                ui.label("  (e.g. hold away from the station at 15 DME with 5 NM legs: turn at 20 DME)");
                 ui.add_space(8.0);
                 ui.strong("Maximum Holding Speeds:");
                 ui.label("  • Below 6,000' MSL: 200 KIAS");
@@ -1,8 +1,15 @@
 use eframe::egui;
-use crate::data::{XPlaneData, HoldingPattern, DisplayMode, radial_to_cardinal, bearing_from_radial, bearing_to_from_radial};
-use crate::calculations::calculate_distance;
+use crate::data::{XPlaneData, HoldingPattern, HoldingFix, DisplayMode, EntryKind, EntryRules, LegLength, radial_to_cardinal, bearing_from_radial, bearing_to_from_radial};
+use crate::calculations::{calculate_bearing, calculate_distance, destination_point, entry_sectors, groundspeed_on_course, holding_wind_correction, magnetic_to_true, true_to_magnetic};
+use crate::geometry::{build_racetrack, from_hold_frame, RacetrackParams};
+use crate::scoring::HoldingBuffer;
 use crate::tile_manager::{TileManager, TileCoord};
 
+pub struct MapActions {
+    pub steer_to: Option<(f64, f64)>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn draw_map(
     ui: &mut egui::Ui,
     xplane: &XPlaneData,
@@ -14,7 +21,10 @@ pub fn draw_map(
     is_dragging: &mut bool,
     drag_start: &mut Option<egui::Pos2>,
     display_mode: DisplayMode,
-) {
+    entry_rules: EntryRules,
+    heading_bug: Option<f64>,
+) -> MapActions {
+    let mut actions = MapActions { steer_to: None };
     let available = ui.available_size();
     let (response, painter) = ui.allocate_painter(available, egui::Sense::click_and_drag());
     let rect = response.rect;
@@ -41,7 +51,7 @@ pub fn draw_map(
 
     painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(20, 25, 30));
 
-    if !holding.active || xplane.vor_lat == 0.0 {
+    if !holding.active || holding.fix_lat == 0.0 {
         painter.text(
             rect.center(),
             egui::Align2::CENTER_CENTER,
@@ -49,11 +59,11 @@ pub fn draw_map(
             egui::FontId::proportional(24.0),
             egui::Color32::GRAY,
         );
-        return;
+        return actions;
     }
 
-    let center_lat = xplane.vor_lat;
-    let center_lon = xplane.vor_lon;
+    let center_lat = holding.fix_lat;
+    let center_lon = holding.fix_lon;
 
     let (center_tile_x, center_tile_y) = TileManager::lat_lon_to_tile(center_lat, center_lon, zoom);
 
@@ -61,8 +71,8 @@ pub fn draw_map(
     let tiles_y = 3;
     let tile_size = 256.0;
 
-    for dy in -(tiles_y as i32)..(tiles_y as i32) {
-        for dx in -(tiles_x as i32)..(tiles_x as i32) {
+    for dy in -tiles_y..tiles_y {
+        for dx in -tiles_x..tiles_x {
             let tile_x = (center_tile_x as i32 + dx) as u32;
             let tile_y = (center_tile_y as i32 + dy) as u32;
 
@@ -91,65 +101,96 @@ pub fn draw_map(
         }
     }
 
-    let vor_pos = lat_lon_to_screen(xplane.vor_lat, xplane.vor_lon, center_lat, center_lon, zoom, &rect, map_offset);
-    let cross_size = 20.0;
-    painter.line_segment(
-        [vor_pos - egui::vec2(cross_size, 0.0), vor_pos + egui::vec2(cross_size, 0.0)],
-        egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)),
-    );
-    painter.line_segment(
-        [vor_pos - egui::vec2(0.0, cross_size), vor_pos + egui::vec2(0.0, cross_size)],
-        egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)),
-    );
-    painter.circle_stroke(vor_pos, 15.0, egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)));
+    let to_screen = |lat: f64, lon: f64| lat_lon_to_screen(lat, lon, center_lat, center_lon, zoom, &rect, map_offset);
 
-    let vor_label = &xplane.vor_id;
-    let font_id = egui::FontId::proportional(18.0);
-    let galley = painter.layout_no_wrap(vor_label.clone(), font_id.clone(), egui::Color32::WHITE);
-    let text_pos = vor_pos + egui::vec2(0.0, -35.0);
-    let text_rect = egui::Align2::CENTER_CENTER.anchor_rect(egui::Rect::from_min_size(text_pos, galley.size()));
-    painter.rect_filled(text_rect.expand(4.0), 3.0, egui::Color32::from_rgba_premultiplied(0, 0, 0, 180));
-    painter.galley(text_rect.min, galley, egui::Color32::WHITE);
+    draw_fix_construction(&painter, &holding.fix, holding.fix_lat, holding.fix_lon, &to_screen);
 
-    if holding.active && !holding.correct_entry.is_empty() {
-        let radial_angle = (holding.radial as f64).to_radians();
+    if xplane.vor_lat != 0.0 {
+        draw_station(&painter, to_screen(xplane.vor_lat, xplane.vor_lon), &xplane.vor_id);
+    }
+    for station in holding.fix.stations() {
+        if station.id != xplane.vor_id {
+            draw_station(&painter, to_screen(station.lat, station.lon), &station.id);
+        }
+    }
+
+    let fix_pos = to_screen(holding.fix_lat, holding.fix_lon);
+    if !matches!(holding.fix, HoldingFix::Station(_)) {
+        draw_fix_marker(&painter, fix_pos, &holding.fix.name());
+    }
+
+    if holding.active && holding.correct_entry.is_some() {
+        let radial_angle = holding.outbound_course.to_radians();
         let radial_length = 250.0;
-        let radial_end = vor_pos + egui::vec2(
+        let radial_end = fix_pos + egui::vec2(
             (radial_angle.sin() * radial_length) as f32,
             -(radial_angle.cos() * radial_length) as f32,
         );
         painter.line_segment(
-            [vor_pos, radial_end],
+            [fix_pos, radial_end],
             egui::Stroke::new(2.5, egui::Color32::from_rgb(0, 180, 255)),
         );
 
         let sector_radius = 180.0;
+        let sectors = entry_sectors(holding.inbound_course, holding.right_turns);
 
-        if holding.right_turns {
-            let direct_start = (holding.outbound_course - 90.0 + 360.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, direct_start.to_radians(), std::f64::consts::PI,
-                               sector_radius, egui::Color32::from_rgba_premultiplied(0, 255, 0, 50), "DIRECT");
-
-            let teardrop_start = (holding.outbound_course + 90.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, teardrop_start.to_radians(), 70.0_f64.to_radians(),
-                               sector_radius, egui::Color32::from_rgba_premultiplied(255, 0, 255, 50), "TEARDROP");
+        for sector in &sectors {
+            let color = match sector.kind {
+                EntryKind::Direct => egui::Color32::from_rgba_premultiplied(0, 255, 0, 50),
+                EntryKind::Teardrop => egui::Color32::from_rgba_premultiplied(255, 0, 255, 50),
+                EntryKind::Parallel => egui::Color32::from_rgba_premultiplied(100, 150, 255, 50),
+            };
+            draw_sector_filled(&painter, fix_pos, sector.bearing_start().to_radians(), sector.width.to_radians(),
+                               sector_radius, color, sector.kind.label());
+        }
 
-            let parallel_start = (holding.outbound_course + 160.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, parallel_start.to_radians(), 110.0_f64.to_radians(),
-                               sector_radius, egui::Color32::from_rgba_premultiplied(100, 150, 255, 50), "PARALLEL");
+        let tolerance = entry_rules.boundary_tolerance();
+        if tolerance > 0.0 {
+            for sector in &sectors {
+                let band_start = sector.bearing_start() - tolerance;
+                draw_sector_filled(&painter, fix_pos, band_start.to_radians(), (2.0 * tolerance).to_radians(),
+                                   sector_radius, egui::Color32::from_rgba_premultiplied(255, 255, 0, 40), "");
+            }
+        }
 
-        } else {
-            let direct_start = (holding.outbound_course - 90.0 + 360.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, direct_start.to_radians(), std::f64::consts::PI,
-                               sector_radius, egui::Color32::from_rgba_premultiplied(0, 255, 0, 50), "DIRECT");
+        let racetrack_params = RacetrackParams::from_holding(holding);
+        let buffer_points: Vec<egui::Pos2> = HoldingBuffer::default()
+            .polygon(&racetrack_params)
+            .iter()
+            .map(|&(lat, lon)| lat_lon_to_screen(lat, lon, center_lat, center_lon, zoom, &rect, map_offset))
+            .collect();
+        painter.add(egui::Shape::closed_line(
+            buffer_points,
+            egui::Stroke::new(1.5, egui::Color32::from_rgba_premultiplied(255, 80, 80, 160)),
+        ));
 
-            let parallel_start = (holding.outbound_course + 90.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, parallel_start.to_radians(), 110.0_f64.to_radians(),
-                               sector_radius, egui::Color32::from_rgba_premultiplied(100, 150, 255, 50), "PARALLEL");
+        let racetrack = build_racetrack(&racetrack_params);
+        let racetrack_points: Vec<egui::Pos2> = racetrack.points
+            .iter()
+            .map(|&(lat, lon)| lat_lon_to_screen(lat, lon, center_lat, center_lon, zoom, &rect, map_offset))
+            .collect();
+        painter.add(egui::Shape::line(
+            racetrack_points,
+            egui::Stroke::new(3.0, egui::Color32::from_rgba_premultiplied(255, 255, 255, 200)),
+        ));
 
-            let teardrop_start = (holding.outbound_course + 200.0) % 360.0;
-            draw_sector_filled(&painter, vor_pos, teardrop_start.to_radians(), 70.0_f64.to_radians(),
-                               sector_radius, egui::Color32::from_rgba_premultiplied(255, 0, 255, 50), "TEARDROP");
+        if let Some(limit) = racetrack_params.dme_limit {
+            let (end_lat, end_lon) = from_hold_frame(
+                -racetrack.leg_length,
+                2.0 * racetrack.turn_radius,
+                holding.fix_lat,
+                holding.fix_lon,
+                holding.inbound_course,
+                holding.right_turns,
+            );
+            let end_bearing = calculate_bearing(limit.station_lat, limit.station_lon, end_lat, end_lon);
+            let arc = dme_arc(limit.station_lat, limit.station_lon, end_bearing, limit.distance, 20, &to_screen);
+            painter.add(egui::Shape::dashed_line(
+                &arc,
+                egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 200, 0)),
+                6.0,
+                4.0,
+            ));
         }
     }
 
@@ -168,10 +209,9 @@ pub fn draw_map(
         painter.circle_stroke(entry_pos, 8.0, egui::Stroke::new(3.0, egui::Color32::from_rgb(200, 200, 0)));
         painter.circle_stroke(entry_pos, 12.0, egui::Stroke::new(2.0, egui::Color32::from_rgba_premultiplied(255, 255, 0, 150)));
 
-        if !holding.correct_entry.is_empty() {
-            let entry_label = &holding.correct_entry;
+        if let Some(correct_entry) = holding.correct_entry {
             let font_id = egui::FontId::monospace(12.0);
-            let galley = painter.layout_no_wrap(entry_label.clone(), font_id.clone(), egui::Color32::WHITE);
+            let galley = painter.layout_no_wrap(correct_entry.label(), font_id.clone(), egui::Color32::WHITE);
             let text_pos = entry_pos + egui::vec2(0.0, -25.0);
             let text_rect = egui::Align2::CENTER_CENTER.anchor_rect(egui::Rect::from_min_size(text_pos, galley.size()));
             painter.rect_filled(text_rect.expand(4.0), 3.0, egui::Color32::from_rgba_premultiplied(0, 0, 0, 200));
@@ -188,6 +228,18 @@ pub fn draw_map(
             egui::Stroke::new(3.0, egui::Color32::from_rgb(255, 255, 0))
         );
         painter.circle_filled(heading_line_end, 4.0, egui::Color32::from_rgb(255, 255, 0));
+
+        if (holding.start_track - holding.start_heading).abs() > 0.5 {
+            let track_rad = holding.start_track.to_radians();
+            let track_line_end = entry_pos + egui::vec2(
+                (track_rad.sin() * 55.0) as f32,
+                -(track_rad.cos() * 55.0) as f32,
+            );
+            painter.line_segment(
+                [entry_pos, track_line_end],
+                egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 160, 40))
+            );
+        }
     }
 
     if holding.track_points.len() > 1 {
@@ -219,11 +271,22 @@ pub fn draw_map(
     }
 
     let aircraft_pos = lat_lon_to_screen(xplane.aircraft_lat, xplane.aircraft_lon, center_lat, center_lon, zoom, &rect, map_offset);
-    draw_aircraft_icon(&painter, aircraft_pos, xplane.aircraft_heading);
+    if let Some(bug) = heading_bug {
+        draw_heading_bug(&painter, aircraft_pos, bug);
+
+        if response.secondary_clicked() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                actions.steer_to = Some(screen_to_lat_lon(pointer, center_lat, center_lon, zoom, &rect, map_offset));
+            }
+        }
+    }
+    draw_aircraft_icon(&painter, aircraft_pos, xplane.aircraft_heading_true);
 
     if show_overlay && holding.active {
         draw_atc_overlay(ui, &painter, &rect, xplane, holding, display_mode);
     }
+
+    actions
 }
 
 pub fn draw_atc_overlay(
@@ -238,15 +301,41 @@ pub fn draw_atc_overlay(
     let overlay_width = 400.0;
 
     let turns_text = if holding.right_turns { "RIGHT TURNS" } else { "LEFT TURNS" };
+    // Courses and headings are kept true; the instruction reads magnetic like a chart.
+    let magnetic = |course: f64| true_to_magnetic(course, holding.declination);
 
     let mut y_offset = 0.0;
     let line_height = 17.0;
 
-    let bg_height = if holding.entry_captured {
-        if !holding.correct_entry.is_empty() { 165.0 } else { 140.0 }
+    let wind = holding.wind.wind_at(xplane.aircraft_alt);
+    let wind_correction = if wind.speed > 0.0 {
+        let inbound_leg_time = match holding.leg {
+            LegLength::Time(seconds) => seconds,
+            // A DME leg is flown over the ground, so it lasts longer into a headwind.
+            LegLength::Distance(nm) => nm * 3600.0 / groundspeed_on_course(holding.inbound_course, holding.true_airspeed, wind).max(1.0),
+        };
+        Some(holding_wind_correction(holding.inbound_course, holding.true_airspeed, wind, inbound_leg_time))
+    } else {
+        None
+    };
+
+    let mut bg_height = if holding.entry_captured {
+        if holding.correct_entry.is_some() { 165.0 } else { 140.0 }
     } else {
         130.0
     };
+    if wind_correction.is_some() {
+        bg_height += line_height * 2.0;
+    }
+    let station_fix = matches!(holding.fix, HoldingFix::Station(_));
+    let fix_name = holding.fix.name();
+    if !station_fix {
+        bg_height += line_height;
+    }
+    let dme_limit = RacetrackParams::from_holding(holding).dme_limit;
+    if dme_limit.is_some() {
+        bg_height += line_height;
+    }
 
     let bg_rect = egui::Rect::from_min_size(overlay_pos, egui::vec2(overlay_width, bg_height));
     painter.rect_filled(bg_rect.expand(8.0), 4.0, egui::Color32::from_rgba_premultiplied(0, 20, 40, 220));
@@ -259,17 +348,45 @@ pub fn draw_atc_overlay(
     painter.galley(text_start + egui::vec2(0.0, y_offset), title_galley, egui::Color32::from_rgb(100, 200, 255));
     y_offset += line_height * 1.4;
 
-    let holding_text = match display_mode {
-        DisplayMode::Radial => {
-            format!("HOLD AT {} R-{:03} {}", xplane.vor_id, holding.radial, turns_text)
+    let ndb_hold = holding.is_ndb_hold();
+    let mut holding_lines = match (display_mode, station_fix) {
+        // NDB holds are cleared on a bearing to the station (QDM), not a radial.
+        (DisplayMode::Radial, true) if ndb_hold => {
+            vec![format!("HOLD AT {} NDB QDM {:03.0}° {} {} LEGS", fix_name, magnetic(holding.inbound_course), turns_text, holding.leg.label())]
+        }
+        (DisplayMode::Cardinal, true) if ndb_hold => {
+            let cardinal = radial_to_cardinal(holding.radial);
+            vec![format!("HOLD {} OF {} NDB {} {} LEGS", cardinal, fix_name, turns_text, holding.leg.label())]
+        }
+        (DisplayMode::Radial, true) => {
+            vec![format!("HOLD AT {} R-{:03} {} {} LEGS", fix_name, holding.radial, turns_text, holding.leg.label())]
+        }
+        (DisplayMode::Cardinal, true) => {
+            let cardinal = radial_to_cardinal(holding.radial);
+            vec![format!("HOLD {} OF {} {} {} LEGS", cardinal, fix_name, turns_text, holding.leg.label())]
         }
-        DisplayMode::Cardinal => {
+        (DisplayMode::Radial, false) => vec![
+            format!("HOLD AT {}", fix_name),
+            format!("INBOUND {:03.0}° {} {} LEGS", magnetic(holding.inbound_course), turns_text, holding.leg.label()),
+        ],
+        (DisplayMode::Cardinal, false) => {
             let cardinal = radial_to_cardinal(holding.radial);
-            format!("HOLD {} OF {} {}", cardinal, xplane.vor_id, turns_text)
+            vec![
+                format!("HOLD {} OF {}", cardinal, fix_name),
+                format!("{} {} LEGS", turns_text, holding.leg.label()),
+            ]
         }
     };
-    let holding_galley = painter.layout_no_wrap(holding_text, egui::FontId::monospace(12.0), egui::Color32::from_rgb(200, 255, 200));
-    painter.galley(text_start + egui::vec2(0.0, y_offset), holding_galley, egui::Color32::from_rgb(200, 255, 200));
+    if let (Some(limit), Some(station)) = (dme_limit, holding.fix.stations().first()) {
+        holding_lines.push(format!("HOLDING {} {}, OUTBOUND END {:.0} DME", limit.side.label(), station.id, limit.distance));
+    }
+    for (index, line) in holding_lines.into_iter().enumerate() {
+        if index > 0 {
+            y_offset += line_height;
+        }
+        let holding_galley = painter.layout_no_wrap(line, egui::FontId::monospace(12.0), egui::Color32::from_rgb(200, 255, 200));
+        painter.galley(text_start + egui::vec2(0.0, y_offset), holding_galley, egui::Color32::from_rgb(200, 255, 200));
+    }
     y_offset += line_height * 1.3;
 
     painter.line_segment(
@@ -280,12 +397,33 @@ pub fn draw_atc_overlay(
 
     let from_bearing = bearing_from_radial(holding.radial);
     let to_bearing = bearing_to_from_radial(holding.radial);
-    let courses_text = format!("IN: {:03.0}°  OUT: {:03.0}° | {:03}° FROM {} | {:03}° TO {}",
-        holding.inbound_course, holding.outbound_course, from_bearing, xplane.vor_id, to_bearing, xplane.vor_id);
+    let courses_text = if ndb_hold {
+        format!("IN: {:03.0}°  OUT: {:03.0}° | QDM {:03.0}° TO {}",
+            magnetic(holding.inbound_course), magnetic(holding.outbound_course), magnetic(holding.inbound_course), fix_name)
+    } else if station_fix {
+        format!("IN: {:03.0}°  OUT: {:03.0}° | {:03}° FROM {} | {:03}° TO {}",
+            magnetic(holding.inbound_course), magnetic(holding.outbound_course), from_bearing, fix_name, to_bearing, fix_name)
+    } else {
+        format!("IN: {:03.0}°  OUT: {:03.0}°", magnetic(holding.inbound_course), magnetic(holding.outbound_course))
+    };
     let courses_galley = painter.layout_no_wrap(courses_text, egui::FontId::monospace(11.0), egui::Color32::from_rgb(180, 200, 220));
     painter.galley(text_start + egui::vec2(0.0, y_offset), courses_galley, egui::Color32::from_rgb(180, 200, 220));
     y_offset += line_height * 1.1;
 
+    if let Some(correction) = wind_correction {
+        let wind_text = format!("WIND {:03.0}/{:.0}  IN HDG {:03.0}° (WCA {:+.0}°) GS {:.0}",
+            wind.direction, wind.speed, magnetic(correction.inbound_heading), correction.inbound_wca, correction.inbound_groundspeed);
+        let wind_galley = painter.layout_no_wrap(wind_text, egui::FontId::monospace(11.0), egui::Color32::from_rgb(255, 210, 140));
+        painter.galley(text_start + egui::vec2(0.0, y_offset), wind_galley, egui::Color32::from_rgb(255, 210, 140));
+        y_offset += line_height;
+
+        let outbound_text = format!("OUT HDG {:03.0}° (3x WCA {:+.0}°) GS {:.0}  TIME {:.0}s",
+            magnetic(correction.outbound_heading), correction.outbound_wca, correction.outbound_groundspeed, correction.outbound_time);
+        let outbound_galley = painter.layout_no_wrap(outbound_text, egui::FontId::monospace(11.0), egui::Color32::from_rgb(255, 210, 140));
+        painter.galley(text_start + egui::vec2(0.0, y_offset), outbound_galley, egui::Color32::from_rgb(255, 210, 140));
+        y_offset += line_height;
+    }
+
     if holding.entry_captured {
         painter.line_segment(
             [text_start + egui::vec2(0.0, y_offset), text_start + egui::vec2(overlay_width - 20.0, y_offset)],
@@ -293,13 +431,20 @@ pub fn draw_atc_overlay(
         );
         y_offset += line_height * 0.7;
 
-        let entry_h = format!("Entry HDG: {:03.0}°", holding.start_heading);
+        let entry_h = if ndb_hold {
+            format!("Entry HDG: {:03.0}°  RB: {:03.0}°  QDM: {:03.0}°",
+                magnetic(holding.start_heading), holding.start_relative_bearing, magnetic(holding.entry_reference()))
+        } else if wind_correction.is_some() {
+            format!("Entry HDG: {:03.0}°  TRK: {:03.0}°", magnetic(holding.start_heading), magnetic(holding.start_track))
+        } else {
+            format!("Entry HDG: {:03.0}°", magnetic(holding.start_heading))
+        };
         let entry_h_galley = painter.layout_no_wrap(entry_h, egui::FontId::monospace(11.0), egui::Color32::from_rgb(200, 220, 255));
         painter.galley(text_start + egui::vec2(0.0, y_offset), entry_h_galley, egui::Color32::from_rgb(200, 220, 255));
         y_offset += line_height * 1.2;
 
-        if !holding.correct_entry.is_empty() {
-            let entry_text = format!(" ENTRY: {} ", holding.correct_entry);
+        if let Some(correct_entry) = holding.correct_entry {
+            let entry_text = format!(" ENTRY: {} ", correct_entry.label());
             let entry_galley = painter.layout_no_wrap(entry_text.clone(), egui::FontId::monospace(12.0), egui::Color32::BLACK);
             let entry_size = entry_galley.size();
 
@@ -314,8 +459,8 @@ pub fn draw_atc_overlay(
         let distance = calculate_distance(
             xplane.aircraft_lat,
             xplane.aircraft_lon,
-            xplane.vor_lat,
-            xplane.vor_lon,
+            holding.fix_lat,
+            holding.fix_lon,
         );
 
         painter.line_segment(
@@ -335,6 +480,100 @@ pub fn draw_atc_overlay(
     }
 }
 
+pub fn draw_station(painter: &egui::Painter, pos: egui::Pos2, label: &str) {
+    let cross_size = 20.0;
+    painter.line_segment(
+        [pos - egui::vec2(cross_size, 0.0), pos + egui::vec2(cross_size, 0.0)],
+        egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)),
+    );
+    painter.line_segment(
+        [pos - egui::vec2(0.0, cross_size), pos + egui::vec2(0.0, cross_size)],
+        egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)),
+    );
+    painter.circle_stroke(pos, 15.0, egui::Stroke::new(4.0, egui::Color32::from_rgb(0, 200, 255)));
+
+    let font_id = egui::FontId::proportional(18.0);
+    let galley = painter.layout_no_wrap(label.to_string(), font_id, egui::Color32::WHITE);
+    let text_pos = pos + egui::vec2(0.0, -35.0);
+    let text_rect = egui::Align2::CENTER_CENTER.anchor_rect(egui::Rect::from_min_size(text_pos, galley.size()));
+    painter.rect_filled(text_rect.expand(4.0), 3.0, egui::Color32::from_rgba_premultiplied(0, 0, 0, 180));
+    painter.galley(text_rect.min, galley, egui::Color32::WHITE);
+}
+
+pub fn draw_fix_marker(painter: &egui::Painter, pos: egui::Pos2, label: &str) {
+    let size = 11.0;
+    let triangle = vec![
+        pos + egui::vec2(0.0, -size),
+        pos + egui::vec2(size * 0.87, size * 0.5),
+        pos + egui::vec2(-size * 0.87, size * 0.5),
+    ];
+    painter.add(egui::Shape::convex_polygon(
+        triangle,
+        egui::Color32::from_rgba_premultiplied(255, 255, 255, 60),
+        egui::Stroke::new(2.5, egui::Color32::WHITE),
+    ));
+
+    let galley = painter.layout_no_wrap(label.to_string(), egui::FontId::monospace(12.0), egui::Color32::WHITE);
+    let text_pos = pos + egui::vec2(0.0, 24.0);
+    let text_rect = egui::Align2::CENTER_CENTER.anchor_rect(egui::Rect::from_min_size(text_pos, galley.size()));
+    painter.rect_filled(text_rect.expand(3.0), 2.0, egui::Color32::from_rgba_premultiplied(0, 0, 0, 180));
+    painter.galley(text_rect.min, galley, egui::Color32::WHITE);
+}
+
+// Radials from the defining stations to the fix, plus the DME arc for radial/DME fixes.
+pub fn draw_fix_construction(
+    painter: &egui::Painter,
+    fix: &HoldingFix,
+    fix_lat: f64,
+    fix_lon: f64,
+    to_screen: &dyn Fn(f64, f64) -> egui::Pos2,
+) {
+    let stroke = egui::Stroke::new(1.5, egui::Color32::from_rgba_premultiplied(0, 200, 255, 180));
+    let fix_pos = to_screen(fix_lat, fix_lon);
+
+    for station in fix.stations() {
+        let station_pos = to_screen(station.lat, station.lon);
+        if station_pos.distance(fix_pos) > 1.0 {
+            let beyond = fix_pos + (fix_pos - station_pos) * 0.2;
+            painter.add(egui::Shape::dashed_line(&[station_pos, beyond], stroke, 8.0, 5.0));
+        }
+    }
+
+    if let HoldingFix::RadialDme { station, radial, distance } = fix {
+        painter.add(egui::Shape::line(dme_arc(station.lat, station.lon, magnetic_to_true(*radial, station.declination), *distance, 15, to_screen), stroke));
+    }
+}
+
+fn dme_arc(
+    station_lat: f64,
+    station_lon: f64,
+    center_bearing: f64,
+    distance: f64,
+    half_span: i32,
+    to_screen: &dyn Fn(f64, f64) -> egui::Pos2,
+) -> Vec<egui::Pos2> {
+    (-half_span..=half_span)
+        .map(|offset| {
+            let (lat, lon) = destination_point(station_lat, station_lon, center_bearing + offset as f64, distance);
+            to_screen(lat, lon)
+        })
+        .collect()
+}
+
+pub fn draw_heading_bug(painter: &egui::Painter, pos: egui::Pos2, heading_bug: f64) {
+    let bug_rad = heading_bug.to_radians();
+    let direction = egui::vec2(bug_rad.sin() as f32, -bug_rad.cos() as f32);
+    let color = egui::Color32::from_rgb(255, 0, 255);
+
+    painter.add(egui::Shape::dashed_line(
+        &[pos, pos + direction * 70.0],
+        egui::Stroke::new(2.0, color),
+        6.0,
+        4.0,
+    ));
+    draw_arrow(painter, pos + direction * 70.0, direction.y.atan2(direction.x), color);
+}
+
 pub fn draw_aircraft_icon(painter: &egui::Painter, pos: egui::Pos2, heading: f64) {
     let heading_rad = heading.to_radians();
 
@@ -390,6 +629,10 @@ pub fn draw_sector_filled(painter: &egui::Painter, center: egui::Pos2, start_ang
         egui::Stroke::new(1.5, egui::Color32::from_rgba_premultiplied(color.r(), color.g(), color.b(), 120)),
     ));
 
+    if label.is_empty() {
+        return;
+    }
+
     let mid_angle = start_angle + arc_angle / 2.0;
     let label_distance = radius * 0.6;
     let label_pos = egui::pos2(
@@ -445,3 +688,17 @@ fn lat_lon_to_screen(lat: f64, lon: f64, center_lat: f64, center_lon: f64, zoom:
 
     egui::pos2(rect.center().x + dx + map_offset.x, rect.center().y + dy + map_offset.y)
 }
+
+fn screen_to_lat_lon(pos: egui::Pos2, center_lat: f64, center_lon: f64, zoom: u8, rect: &egui::Rect, map_offset: &egui::Vec2) -> (f64, f64) {
+    let scale = 2_f64.powi(zoom as i32) * 256.0;
+
+    let center_x = (center_lon + 180.0) / 360.0 * scale;
+    let center_y = (1.0 - (center_lat.to_radians().tan() + 1.0 / center_lat.to_radians().cos()).ln() / std::f64::consts::PI) / 2.0 * scale;
+
+    let point_x = center_x + (pos.x - rect.center().x - map_offset.x) as f64;
+    let point_y = center_y + (pos.y - rect.center().y - map_offset.y) as f64;
+
+    let lon = point_x / scale * 360.0 - 180.0;
+    let lat = ((1.0 - 2.0 * point_y / scale) * std::f64::consts::PI).sinh().atan().to_degrees();
+    (lat, lon)
+}
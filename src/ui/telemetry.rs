@@ -1,82 +1,68 @@
 use eframe::egui;
-use std::collections::HashSet;
-use crate::data::{XPlaneData, HoldingPattern, VorInfo, DisplayMode};
-use crate::calculations::calculate_distance;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::data::{XPlaneData, SourceKind, HoldingPattern, HoldingFix, VorInfo, NavaidKind, DisplayMode, EntryRules, LegLength, Wind, WindModel};
+use crate::analysis::analyze_flown_entry;
+use crate::calculations::{calculate_bearing, calculate_distance, magnetic_to_true, relative_bearing, true_to_magnetic};
+use crate::flight_model::FlightModel;
+use crate::navdata::{self, ValidationReport};
+use crate::navindex::NavaidIndex;
+use crate::stations::{self, SavedStations};
+use crate::sim_clock::{SimClock, TIME_RATES};
+use crate::sources::{recording_path, ConnectionState, LiveLink, SourceSettings, BRIDGE_PATH_VAR};
+use crate::geometry::{build_racetrack, RacetrackParams};
+use crate::scoring::{score_holding, HoldingBuffer};
 
 pub struct SimulatedTelemetryActions {
     pub generate_position: bool,
     pub calculate_result: bool,
     pub change_vor: Option<usize>,
+    pub toggle_favourite: Option<usize>,
+    pub import_navaids: bool,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_simulated_telemetry(
     ui: &mut egui::Ui,
-    simulated_data: &mut XPlaneData,
-    simulated_holding: &HoldingPattern,
-    available_vors: &[VorInfo],
+    simulated_data: &XPlaneData,
+    simulated_holding: &mut HoldingPattern,
+    flight_model: &mut FlightModel,
+    sim_clock: &mut SimClock,
+    scrub_time: &mut Option<f64>,
+    available_vors: &NavaidIndex,
     selected_vor_index: usize,
-    country_filter: &mut String,
+    navaid_path: &mut String,
+    navaid_status: &Option<String>,
+    navaid_report: &ValidationReport,
+    station_query: &mut String,
+    saved_stations: &SavedStations,
     zoom: &mut u8,
     display_mode: &mut DisplayMode,
+    entry_rules: &mut EntryRules,
 ) -> SimulatedTelemetryActions {
     let mut actions = SimulatedTelemetryActions {
         generate_position: false,
         calculate_result: false,
         change_vor: None,
+        toggle_favourite: None,
+        import_navaids: false,
     };
 
     ui.heading(egui::RichText::new("VOR Selection").size(16.0));
     ui.add_space(8.0);
+    draw_station_picker(ui, available_vors, selected_vor_index, station_query, saved_stations, &mut actions);
 
-    let countries: Vec<String> = {
-        let mut countries_set = HashSet::new();
-        countries_set.insert("All".to_string());
-        for vor in available_vors {
-            countries_set.insert(vor.country.clone());
-        }
-        let mut countries: Vec<String> = countries_set.into_iter().collect();
-        countries.sort();
-        countries
-    };
-
-    ui.horizontal(|ui| {
-        ui.label(egui::RichText::new("Country:").size(13.0));
-        egui::ComboBox::new("country_filter", "")
-            .selected_text(&*country_filter)
-            .width(200.0)
-            .show_ui(ui, |ui| {
-                for country in &countries {
-                    ui.selectable_value(country_filter, country.clone(), country);
-                }
-            });
-    });
-
-    ui.add_space(12.0);
-
-    let filtered_vors: Vec<(usize, &VorInfo)> = available_vors
-        .iter()
-        .enumerate()
-        .filter(|(_, vor)| *country_filter == "All" || vor.country == *country_filter)
-        .collect();
-
-    let current_vor = &available_vors[selected_vor_index];
-    let current_vor_display = format!("{} - {}", current_vor.id, current_vor.name);
-
+    ui.add_space(8.0);
     ui.horizontal(|ui| {
-        ui.label(egui::RichText::new("VOR:").size(13.0));
-        egui::ComboBox::new("vor_selector", "")
-            .selected_text(&current_vor_display)
-            .width(200.0)
-            .show_ui(ui, |ui| {
-                ui.set_min_width(250.0);
-                for (idx, vor) in &filtered_vors {
-                    let vor_display = format!("{} - {}", vor.id, vor.name);
-                    if ui.selectable_label(selected_vor_index == *idx, &vor_display).clicked() {
-                        actions.change_vor = Some(*idx);
-                    }
-                }
-            });
+        ui.label(egui::RichText::new("earth_nav.dat:").size(13.0));
+        ui.add(egui::TextEdit::singleline(navaid_path).desired_width(140.0).hint_text("path to file"));
+        if ui.button("Import").clicked() {
+            actions.import_navaids = true;
+        }
     });
+    if let Some(status) = navaid_status {
+        ui.label(egui::RichText::new(status).size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+    }
+    draw_validation_report(ui, navaid_report);
 
     ui.add_space(16.0);
     ui.separator();
@@ -100,16 +86,109 @@ pub fn draw_simulated_telemetry(
         });
     }
 
+    ui.add_space(12.0);
+    ui.horizontal(|ui| {
+        let pause_label = if sim_clock.paused { "▶ Resume" } else { "⏸ Pause" };
+        if ui.add_sized([80.0, 24.0], egui::Button::new(pause_label)).clicked() {
+            sim_clock.paused = !sim_clock.paused;
+        }
+        for rate in TIME_RATES {
+            ui.selectable_value(&mut sim_clock.rate, rate, format!("{:.0}x", rate));
+        }
+    });
+    ui.label(egui::RichText::new(format!("Sim time: {:.0}s", scrub_time.unwrap_or(sim_clock.now()))).size(13.0));
+
+    if let (Some(first), Some(last)) = (simulated_holding.track_points.first(), simulated_holding.track_points.last()) {
+        if last.time > first.time {
+            let mut time = scrub_time.unwrap_or(last.time);
+            let slider = egui::Slider::new(&mut time, first.time..=last.time).suffix(" s").text("Rewind");
+            if ui.add(slider).changed() {
+                sim_clock.paused = true;
+                *scrub_time = Some(time);
+            }
+            if scrub_time.is_some() {
+                ui.label(egui::RichText::new("Resume to fly on from this point").size(12.0).color(egui::Color32::from_rgb(255, 200, 120)));
+            }
+        }
+    }
+
     ui.add_space(16.0);
     ui.separator();
     ui.add_space(12.0);
 
-    ui.label(egui::RichText::new("Aircraft Heading:").size(15.0));
+    ui.label(egui::RichText::new("Fly:").size(15.0));
     ui.add_space(6.0);
-    let mut heading = simulated_data.aircraft_heading as f32;
-    if ui.add(egui::Slider::new(&mut heading, 0.0..=359.0).suffix("°")).changed() {
-        simulated_data.aircraft_heading = heading as f64;
+    // The model flies true headings; the bug is set and read magnetic like in the cockpit.
+    let variation = simulated_data.magnetic_variation;
+    let mut heading_bug = true_to_magnetic(flight_model.heading_bug, variation).round() % 360.0;
+    ui.horizontal(|ui| {
+        ui.label("HDG bug");
+        if ui.add(egui::Slider::new(&mut heading_bug, 0.0..=359.0).step_by(1.0).suffix("°")).changed() {
+            flight_model.set_heading_bug(magnetic_to_true(heading_bug, variation));
+        }
+    });
+    ui.horizontal(|ui| {
+        for (label, delta) in [("◀ 10", -10.0), ("◀ 1", -1.0), ("1 ▶", 1.0), ("10 ▶", 10.0)] {
+            if ui.add_sized([50.0, 24.0], egui::Button::new(label)).clicked() {
+                flight_model.turn_bug(delta);
+            }
+        }
+        if ui.button("Sync").on_hover_text("Set the bug to the current heading").clicked() {
+            flight_model.set_heading_bug(flight_model.heading);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Max bank");
+        for bank in [15.0, 20.0, 25.0, 30.0] {
+            ui.selectable_value(&mut simulated_holding.bank_angle, bank, format!("{:.0}°", bank));
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("TAS");
+        ui.add(egui::Slider::new(&mut simulated_holding.true_airspeed, 90.0..=280.0).step_by(5.0).suffix(" kt"));
+    });
+    ui.label(egui::RichText::new(format!("HDG {:03.0}°  Bank {:.0}°  TAS {:.0} kt", true_to_magnetic(flight_model.heading, variation), flight_model.bank, flight_model.true_airspeed)).size(13.0));
+    ui.label(egui::RichText::new("←/→ turn 10° (Shift: 1°), Space pauses, right-click the map to steer there").size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+
+    ui.add_space(16.0);
+    ui.separator();
+    ui.add_space(12.0);
+
+    ui.label(egui::RichText::new("Wind:").size(15.0));
+    ui.add_space(6.0);
+    let mut wind = simulated_holding.wind.wind_at(simulated_data.aircraft_alt);
+    let mut wind_changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Dir");
+        wind_changed |= ui.add(egui::Slider::new(&mut wind.direction, 0.0..=359.0).step_by(5.0).suffix("°")).changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Spd");
+        wind_changed |= ui.add(egui::Slider::new(&mut wind.speed, 0.0..=60.0).step_by(1.0).suffix(" kt")).changed();
+    });
+    if wind_changed {
+        simulated_holding.wind = WindModel::uniform(wind);
     }
+    ui.horizontal(|ui| {
+        if ui.button("Calm").clicked() {
+            simulated_holding.wind = WindModel::uniform(Wind::default());
+        }
+        if ui.button("Random").clicked() {
+            use rand::Rng;
+            let mut rng = rand::thread_rng();
+            simulated_holding.wind = WindModel::uniform(Wind {
+                direction: (rng.gen_range(0..72) * 5) as f64,
+                speed: rng.gen_range(5..=40) as f64,
+            });
+        }
+    });
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        ui.label("Legs");
+        for leg in [LegLength::Time(60.0), LegLength::Time(90.0), LegLength::Distance(4.0), LegLength::Distance(5.0)] {
+            ui.selectable_value(&mut simulated_holding.leg, leg, leg.label());
+        }
+    });
 
     ui.add_space(16.0);
     ui.separator();
@@ -134,6 +213,12 @@ pub fn draw_simulated_telemetry(
     ui.separator();
     ui.add_space(12.0);
 
+    draw_rules_selector(ui, entry_rules);
+
+    ui.add_space(16.0);
+    ui.separator();
+    ui.add_space(12.0);
+
     ui.label(egui::RichText::new("Map Zoom:").size(15.0));
     ui.add_space(6.0);
     ui.horizontal(|ui| {
@@ -157,9 +242,8 @@ pub fn draw_simulated_telemetry(
     ui.add_space(10.0);
 
     ui.group(|ui| {
-        ui.label(egui::RichText::new(format!("VOR: {}", simulated_data.vor_id)).size(15.0).color(egui::Color32::from_rgb(0, 200, 255)));
-        ui.add_space(3.0);
-        ui.label(egui::RichText::new(format!("Freq: {:.2} MHz", simulated_data.vor_freq as f64 / 100.0)).size(14.0).color(egui::Color32::from_rgb(100, 220, 255)));
+        let ndb = available_vors.navaids()[selected_vor_index].kind == NavaidKind::Ndb;
+        draw_tuned_station(ui, simulated_data, ndb);
 
         ui.add_space(8.0);
         ui.separator();
@@ -172,14 +256,21 @@ pub fn draw_simulated_telemetry(
             simulated_data.vor_lon,
         );
         ui.label(egui::RichText::new(format!("Distance: {:.2} NM", distance)).size(15.0).color(egui::Color32::from_rgb(100, 255, 255)));
+        if simulated_holding.active && !matches!(simulated_holding.fix, HoldingFix::Station(_)) {
+            let fix_distance = calculate_distance(
+                simulated_data.aircraft_lat,
+                simulated_data.aircraft_lon,
+                simulated_holding.fix_lat,
+                simulated_holding.fix_lon,
+            );
+            ui.label(egui::RichText::new(format!("Fix {}: {:.2} NM", simulated_holding.fix.name(), fix_distance)).size(13.0).color(egui::Color32::from_rgb(100, 255, 255)));
+        }
 
         ui.add_space(8.0);
         ui.separator();
         ui.add_space(8.0);
 
-        ui.label(egui::RichText::new(format!("Heading: {:03.0}°", simulated_data.aircraft_heading)).size(14.0).color(egui::Color32::from_rgb(255, 165, 0)));
-        ui.add_space(3.0);
-        ui.label(egui::RichText::new(format!("Speed: {:.0} kts", simulated_data.aircraft_groundspeed)).size(14.0).color(egui::Color32::from_rgb(255, 165, 0)));
+        draw_flight_data(ui, simulated_data);
 
         ui.add_space(8.0);
         ui.separator();
@@ -188,6 +279,13 @@ pub fn draw_simulated_telemetry(
         ui.label(egui::RichText::new(format!("Lat: {:.6}°  Lon: {:.6}°", simulated_data.aircraft_lat, simulated_data.aircraft_lon)).size(13.0));
     });
 
+    ui.add_space(16.0);
+
+    match *scrub_time {
+        Some(time) => draw_tracking(ui, &simulated_holding.truncated_at(time)),
+        None => draw_tracking(ui, simulated_holding),
+    }
+
     ui.add_space(20.0);
     ui.separator();
     ui.add_space(8.0);
@@ -207,29 +305,168 @@ pub fn draw_simulated_telemetry(
     actions
 }
 
+fn draw_validation_report(ui: &mut egui::Ui, report: &ValidationReport) {
+    let title = match report.issues.len() {
+        0 => "Navaid files: no problems".to_string(),
+        count => format!("Navaid files: {} problem{}", count, if count == 1 { "" } else { "s" }),
+    };
+    egui::CollapsingHeader::new(egui::RichText::new(title).size(12.0))
+        .id_salt("navaid_report")
+        .show(ui, |ui| {
+            let directory = navdata::data_directory();
+            ui.label(egui::RichText::new(format!("Data directory: {} (set {})", directory.display(), navdata::DATA_DIR_VAR)).size(11.0));
+            for source in &report.sources {
+                ui.label(egui::RichText::new(format!("• {}", source)).size(11.0));
+            }
+            egui::ScrollArea::vertical().id_salt("navaid_issues").max_height(150.0).show(ui, |ui| {
+                for issue in &report.issues {
+                    ui.label(egui::RichText::new(issue).size(11.0).color(egui::Color32::from_rgb(255, 200, 120)));
+                }
+            });
+        });
+}
+
+// Search by ident, name, country or frequency; with an empty search box the
+// favourites and recently used stations are listed instead.
+fn draw_station_picker(
+    ui: &mut egui::Ui,
+    available_vors: &NavaidIndex,
+    selected_vor_index: usize,
+    station_query: &mut String,
+    saved_stations: &SavedStations,
+    actions: &mut SimulatedTelemetryActions,
+) {
+    let navaids = available_vors.navaids();
+    let current_vor = &navaids[selected_vor_index];
+    ui.horizontal(|ui| {
+        if star_button(ui, saved_stations.is_favourite(current_vor)).clicked() {
+            actions.toggle_favourite = Some(selected_vor_index);
+        }
+        ui.label(egui::RichText::new(format!("{}  {}", navaid_display(current_vor), current_vor.frequency_label())).size(13.0).strong());
+    });
+    ui.add_space(6.0);
+    ui.add(egui::TextEdit::singleline(station_query).desired_width(280.0).hint_text("Search ident, name, country or frequency"));
+    ui.add_space(6.0);
+
+    let sections = if station_query.trim().is_empty() {
+        vec![
+            ("Favourites", stations::resolve(&saved_stations.favourites, available_vors)),
+            ("Recent", stations::resolve(&saved_stations.recents, available_vors)),
+        ]
+    } else {
+        vec![("Matches", stations::search(navaids, station_query, 40))]
+    };
+
+    let gray = egui::Color32::from_rgb(150, 150, 150);
+    egui::ScrollArea::vertical().id_salt("station_picker").max_height(200.0).show(ui, |ui| {
+        for (title, indices) in &sections {
+            ui.label(egui::RichText::new(*title).size(12.0).color(gray));
+            if indices.is_empty() {
+                let hint = if station_query.trim().is_empty() { "None yet" } else { "No station matches" };
+                ui.label(egui::RichText::new(hint).size(12.0).italics());
+            }
+            for &index in indices {
+                let vor = &navaids[index];
+                ui.horizontal(|ui| {
+                    if star_button(ui, saved_stations.is_favourite(vor)).clicked() {
+                        actions.toggle_favourite = Some(index);
+                    }
+                    let text = format!("{}  {}  {}", navaid_display(vor), vor.country, vor.frequency_label());
+                    if ui.selectable_label(index == selected_vor_index, text).clicked() {
+                        actions.change_vor = Some(index);
+                    }
+                });
+            }
+            ui.add_space(4.0);
+        }
+    });
+}
+
+fn star_button(ui: &mut egui::Ui, favourite: bool) -> egui::Response {
+    let (star, color) = if favourite { ("★", egui::Color32::from_rgb(255, 210, 80)) } else { ("☆", egui::Color32::from_rgb(150, 150, 150)) };
+    ui.add(egui::Button::new(egui::RichText::new(star).color(color)).frame(false))
+        .on_hover_text(if favourite { "Remove from favourites" } else { "Add to favourites" })
+}
+
+fn navaid_display(vor: &VorInfo) -> String {
+    match vor.kind {
+        NavaidKind::Vor => format!("{} - {}", vor.id, vor.name),
+        kind => format!("{} - {} ({})", vor.id, vor.name, kind.label()),
+    }
+}
+
+fn draw_nearby_navaids(ui: &mut egui::Ui, nearby_navaids: &[(&VorInfo, f64)]) {
+    if nearby_navaids.is_empty() {
+        return;
+    }
+    ui.add_space(8.0);
+    ui.label(egui::RichText::new("Nearest navaids:").size(13.0));
+    for (vor, distance) in nearby_navaids {
+        ui.label(egui::RichText::new(format!("{}  {}  {:.0} NM", navaid_display(vor), vor.frequency_label(), distance)).size(12.0));
+    }
+}
+
+// Arrow keys nudge the heading bug and Space pauses while no text field or slider has focus.
+pub fn handle_fly_keys(ctx: &egui::Context, flight_model: &mut FlightModel, sim_clock: &mut SimClock) {
+    if ctx.memory(|memory| memory.focused().is_some()) {
+        return;
+    }
+
+    let (left, right, fine, pause) = ctx.input(|input| {
+        (
+            input.key_pressed(egui::Key::ArrowLeft),
+            input.key_pressed(egui::Key::ArrowRight),
+            input.modifiers.shift,
+            input.key_pressed(egui::Key::Space),
+        )
+    });
+    if pause {
+        sim_clock.paused = !sim_clock.paused;
+    }
+    let step = if fine { 1.0 } else { 10.0 };
+    if left {
+        flight_model.turn_bug(-step);
+    }
+    if right {
+        flight_model.turn_bug(step);
+    }
+}
+
 pub struct TelemetryActions {
     pub generate_holding: bool,
     pub calculate_result: bool,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_telemetry(
     ui: &mut egui::Ui,
     xplane: &XPlaneData,
     holding: &HoldingPattern,
     zoom: &mut u8,
     display_mode: &mut DisplayMode,
+    entry_rules: &mut EntryRules,
+    live_link: &mut LiveLink,
+    source_settings: &mut SourceSettings,
+    nearby_navaids: &[(&VorInfo, f64)],
 ) -> TelemetryActions {
     let mut actions = TelemetryActions {
         generate_holding: false,
         calculate_result: false,
     };
 
+    draw_source_selector(ui, live_link, source_settings);
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.add_space(12.0);
+
     if !holding.active {
         if ui.add_sized([280.0, 50.0], egui::Button::new(egui::RichText::new("New Holding").size(16.0))).clicked() {
             actions.generate_holding = true;
         }
         ui.add_space(12.0);
         ui.label(egui::RichText::new("Tune a VOR in NAV1").size(14.0));
+        draw_nearby_navaids(ui, nearby_navaids);
         return actions;
     } else {
         ui.horizontal(|ui| {
@@ -271,6 +508,12 @@ pub fn draw_telemetry(
     ui.separator();
     ui.add_space(12.0);
 
+    draw_rules_selector(ui, entry_rules);
+
+    ui.add_space(16.0);
+    ui.separator();
+    ui.add_space(12.0);
+
     ui.label(egui::RichText::new("Map Zoom:").size(15.0));
     ui.add_space(6.0);
     ui.horizontal(|ui| {
@@ -294,9 +537,7 @@ pub fn draw_telemetry(
     ui.add_space(10.0);
 
     ui.group(|ui| {
-        ui.label(egui::RichText::new(format!("VOR: {}", xplane.vor_id)).size(15.0).color(egui::Color32::from_rgb(0, 200, 255)));
-        ui.add_space(3.0);
-        ui.label(egui::RichText::new(format!("Freq: {:.2} MHz", xplane.vor_freq as f64 / 100.0)).size(14.0).color(egui::Color32::from_rgb(100, 220, 255)));
+        draw_tuned_station(ui, xplane, holding.is_ndb_hold());
 
         ui.add_space(8.0);
         ui.separator();
@@ -309,14 +550,21 @@ pub fn draw_telemetry(
             xplane.vor_lon,
         );
         ui.label(egui::RichText::new(format!("Distance: {:.2} NM", distance)).size(15.0).color(egui::Color32::from_rgb(100, 255, 255)));
+        if holding.active && !matches!(holding.fix, HoldingFix::Station(_)) {
+            let fix_distance = calculate_distance(
+                xplane.aircraft_lat,
+                xplane.aircraft_lon,
+                holding.fix_lat,
+                holding.fix_lon,
+            );
+            ui.label(egui::RichText::new(format!("Fix {}: {:.2} NM", holding.fix.name(), fix_distance)).size(13.0).color(egui::Color32::from_rgb(100, 255, 255)));
+        }
 
         ui.add_space(8.0);
         ui.separator();
         ui.add_space(8.0);
 
-        ui.label(egui::RichText::new(format!("Heading: {:03.0}°", xplane.aircraft_heading)).size(14.0).color(egui::Color32::from_rgb(255, 165, 0)));
-        ui.add_space(3.0);
-        ui.label(egui::RichText::new(format!("Speed: {:.0} kts", xplane.aircraft_groundspeed)).size(14.0).color(egui::Color32::from_rgb(255, 165, 0)));
+        draw_flight_data(ui, xplane);
 
         ui.add_space(8.0);
         ui.separator();
@@ -327,26 +575,7 @@ pub fn draw_telemetry(
 
     ui.add_space(16.0);
 
-    if holding.entry_captured {
-        ui.add_space(16.0);
-        ui.heading(egui::RichText::new("Tracking").size(18.0));
-        ui.add_space(10.0);
-
-        ui.group(|ui| {
-            ui.label(egui::RichText::new(format!("Points: {}", holding.track_points.len())).size(14.0));
-            ui.add_space(3.0);
-            if let (Some(first), Some(last)) = (holding.track_points.first(), holding.track_points.last()) {
-                ui.label(egui::RichText::new(format!("Time: {:.1}s", last.time - first.time)).size(14.0));
-            }
-
-            if !holding.track_points.is_empty() {
-                if let Some(last) = holding.track_points.last() {
-                    ui.add_space(8.0);
-                    ui.label(egui::RichText::new(format!("Last point: {:.6}, {:.6}", last.lat, last.lon)).size(13.0));
-                }
-            }
-        });
-    }
+    draw_tracking(ui, holding);
 
     ui.add_space(20.0);
     ui.separator();
@@ -366,3 +595,315 @@ pub fn draw_telemetry(
 
     actions
 }
+
+// The station the hold is flown on: ADF1 over an NDB, NAV1 otherwise.
+fn draw_tuned_station(ui: &mut egui::Ui, data: &XPlaneData, ndb: bool) {
+    let (label, id, frequency) = if ndb {
+        ("NDB", &data.adf_id, NavaidKind::Ndb.frequency_label(data.adf_freq))
+    } else {
+        ("VOR", &data.vor_id, NavaidKind::Vor.frequency_label(data.vor_freq))
+    };
+    ui.label(egui::RichText::new(format!("{}: {}", label, id)).size(15.0).color(egui::Color32::from_rgb(0, 200, 255)));
+    ui.add_space(3.0);
+    ui.label(egui::RichText::new(format!("Freq: {}", frequency)).size(14.0).color(egui::Color32::from_rgb(100, 220, 255)));
+}
+
+fn draw_tracking(ui: &mut egui::Ui, holding: &HoldingPattern) {
+    if !holding.entry_captured {
+        return;
+    }
+
+    ui.add_space(16.0);
+    ui.heading(egui::RichText::new("Tracking").size(18.0));
+    ui.add_space(10.0);
+
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(format!("Points: {}", holding.track_points.len())).size(14.0));
+        ui.add_space(3.0);
+        if let (Some(first), Some(last)) = (holding.track_points.first(), holding.track_points.last()) {
+            ui.label(egui::RichText::new(format!("Time: {:.1}s", last.time - first.time)).size(14.0));
+        }
+
+        if !holding.track_points.is_empty() {
+            if let Some(last) = holding.track_points.last() {
+                ui.add_space(8.0);
+                ui.label(egui::RichText::new(format!("Last point: {:.6}, {:.6}", last.lat, last.lon)).size(13.0));
+            }
+        }
+
+        if holding.correct_entry.is_some() {
+            let racetrack_params = RacetrackParams::from_holding(holding);
+            let racetrack = build_racetrack(&racetrack_params);
+            ui.add_space(8.0);
+            ui.label(egui::RichText::new(format!("Turn radius: {:.2} NM  Leg: {:.1} NM", racetrack.turn_radius, racetrack.leg_length)).size(13.0));
+            if let Some(last) = holding.track_points.last() {
+                ui.label(egui::RichText::new(format!("Off nominal hold: {:.2} NM", racetrack.distance_to(last.lat, last.lon))).size(13.0));
+                if let Some(limit) = racetrack_params.dme_limit {
+                    let range = calculate_distance(last.lat, last.lon, limit.station_lat, limit.station_lon);
+                    ui.label(egui::RichText::new(format!("Outbound limit: {:.1} DME  Now: {:.1} DME", limit.distance, range)).size(13.0));
+                }
+            }
+        }
+
+        if holding.correct_entry.is_some() {
+            let report = analyze_flown_entry(holding);
+
+            ui.add_space(8.0);
+            ui.separator();
+            ui.add_space(8.0);
+
+            match report.flown_entry {
+                Some(flown) => {
+                    let (verdict, color) = if report.matched {
+                        ("CORRECT", egui::Color32::from_rgb(100, 255, 100))
+                    } else {
+                        ("WRONG", egui::Color32::from_rgb(255, 100, 100))
+                    };
+                    ui.label(egui::RichText::new(format!("Flown: {} ({})", flown.label(), verdict)).size(15.0).strong().color(color));
+                }
+                None => {
+                    ui.label(egui::RichText::new("Flown: not recognised yet").size(15.0).color(egui::Color32::from_rgb(180, 180, 180)));
+                }
+            }
+
+            if let Some(right) = report.first_turn_right {
+                ui.label(egui::RichText::new(format!("First turn: {}", if right { "RIGHT" } else { "LEFT" })).size(13.0));
+            }
+            if let (Some(offset), Some(time)) = (report.outbound_offset, report.outbound_time) {
+                ui.label(egui::RichText::new(format!("Outbound offset: {:+.0}°  Time: {:.0}s", offset, time)).size(13.0));
+            }
+            ui.label(egui::RichText::new(format!("Inbound intercepted: {}", if report.intercepted { "YES" } else { "NO" })).size(13.0));
+
+            for deviation in &report.deviations {
+                ui.label(egui::RichText::new(format!("• {}", deviation)).size(12.0).color(egui::Color32::from_rgb(255, 200, 120)));
+            }
+        }
+
+        if holding.correct_entry.is_some() {
+            if let Some(score) = score_holding(holding, &HoldingBuffer::default()) {
+                ui.add_space(8.0);
+                ui.separator();
+                ui.add_space(8.0);
+
+                ui.label(egui::RichText::new(format!("Score: {:.0}/100", score.total)).size(15.0).strong().color(egui::Color32::from_rgb(100, 200, 255)));
+                for item in &score.items {
+                    ui.label(egui::RichText::new(format!("{}: {} (-{:.0})", item.label, item.measured, item.penalty)).size(12.0));
+                }
+            }
+        }
+    });
+}
+
+fn draw_flight_data(ui: &mut egui::Ui, data: &XPlaneData) {
+    let orange = egui::Color32::from_rgb(255, 165, 0);
+    ui.label(egui::RichText::new(format!("Heading: {:03.0}° ({:03.0}°T)", data.aircraft_heading, data.aircraft_heading_true)).size(14.0).color(orange));
+    ui.add_space(3.0);
+    ui.label(egui::RichText::new(format!("Speed: {:.0} kts GS, {:.0} KIAS", data.aircraft_groundspeed, data.indicated_airspeed)).size(14.0).color(orange));
+    ui.add_space(3.0);
+
+    let variation_side = if data.magnetic_variation < 0.0 { "W" } else { "E" };
+    ui.label(egui::RichText::new(format!("Track: {:03.0}°T  Var: {:.1}°{}", data.aircraft_track, data.magnetic_variation.abs(), variation_side)).size(13.0));
+    let bank_side = if data.bank_angle < 0.0 { "L" } else { "R" };
+    ui.label(egui::RichText::new(format!("Bank: {:.0}° {}  VS: {:+.0} fpm", data.bank_angle.abs(), bank_side, data.vertical_speed)).size(13.0));
+    ui.label(egui::RichText::new(format!("Wind: {:03.0}°/{:.0} kts", data.wind_direction, data.wind_speed)).size(13.0));
+
+    let bearing_to = |lat: f64, lon: f64| calculate_bearing(data.aircraft_lat, data.aircraft_lon, lat, lon);
+    let adf = (!data.adf_id.is_empty() && data.adf_lat != 0.0).then(|| bearing_to(data.adf_lat, data.adf_lon));
+    // In the simulator a selected NDB is also the "VOR"; one needle is enough.
+    let vor_is_adf = data.vor_id == data.adf_id && data.vor_lat == data.adf_lat;
+    let vor = (data.vor_lat != 0.0 && !vor_is_adf).then(|| bearing_to(data.vor_lat, data.vor_lon));
+    if adf.is_none() && vor.is_none() {
+        return;
+    }
+
+    ui.add_space(6.0);
+    draw_rmi(ui, data, adf, vor);
+    if let Some(bearing) = adf {
+        let relative = relative_bearing(data.aircraft_heading_true, bearing);
+        let qdm = true_to_magnetic(bearing, data.magnetic_variation);
+        ui.label(egui::RichText::new(format!("ADF {} {} kHz  RB {:03.0}°  QDM {:03.0}°", data.adf_id, data.adf_freq, relative, qdm))
+            .size(13.0)
+            .color(egui::Color32::from_rgb(255, 210, 80)));
+    }
+}
+
+// Radio magnetic indicator: the card turns with the magnetic heading, the yellow needle
+// points at the NDB on ADF1 and the green one at the NAV1 station.
+fn draw_rmi(ui: &mut egui::Ui, data: &XPlaneData, adf_bearing: Option<f64>, vor_bearing: Option<f64>) {
+    let size = 140.0;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let center = rect.center();
+    let radius = size / 2.0 - 4.0;
+    // Screen direction of a true bearing, with the nose at the top.
+    let direction = |bearing: f64| {
+        let angle = relative_bearing(data.aircraft_heading_true, bearing).to_radians() as f32;
+        egui::vec2(angle.sin(), -angle.cos())
+    };
+
+    painter.circle_filled(center, radius, egui::Color32::from_rgb(20, 25, 30));
+    painter.circle_stroke(center, radius, egui::Stroke::new(2.0, egui::Color32::from_rgb(120, 120, 120)));
+    for tick in (0..360).step_by(10) {
+        let dir = direction(magnetic_to_true(tick as f64, data.magnetic_variation));
+        let length = if tick % 30 == 0 { 10.0 } else { 5.0 };
+        painter.line_segment([center + dir * radius, center + dir * (radius - length)], egui::Stroke::new(1.5, egui::Color32::WHITE));
+        if tick % 30 == 0 {
+            let label = match tick {
+                0 => "N".to_string(),
+                90 => "E".to_string(),
+                180 => "S".to_string(),
+                270 => "W".to_string(),
+                _ => (tick / 10).to_string(),
+            };
+            painter.text(center + dir * (radius - 20.0), egui::Align2::CENTER_CENTER, label, egui::FontId::proportional(11.0), egui::Color32::WHITE);
+        }
+    }
+    // Lubber line.
+    painter.line_segment(
+        [center + egui::vec2(0.0, -radius - 2.0), center + egui::vec2(0.0, -radius + 12.0)],
+        egui::Stroke::new(3.0, egui::Color32::from_rgb(255, 165, 0)),
+    );
+
+    for (bearing, color, width) in [
+        (vor_bearing, egui::Color32::from_rgb(80, 220, 120), 2.0),
+        (adf_bearing, egui::Color32::from_rgb(255, 210, 80), 3.0),
+    ] {
+        if let Some(bearing) = bearing {
+            let dir = direction(bearing);
+            let head = center + dir * (radius - 28.0);
+            let tail = center - dir * (radius - 28.0);
+            painter.line_segment([tail, head], egui::Stroke::new(width, color));
+            let side = egui::vec2(-dir.y, dir.x) * 6.0;
+            painter.add(egui::Shape::convex_polygon(vec![head + dir * 10.0, head + side, head - side], color, egui::Stroke::NONE));
+        }
+    }
+    painter.circle_filled(center, 4.0, egui::Color32::from_rgb(200, 200, 200));
+}
+
+fn draw_source_selector(ui: &mut egui::Ui, live_link: &mut LiveLink, settings: &mut SourceSettings) {
+    ui.label(egui::RichText::new("Data Source:").size(15.0));
+    ui.add_space(6.0);
+
+    ui.horizontal_wrapped(|ui| {
+        for (index, label) in SOURCE_LABELS.iter().enumerate() {
+            let choice = source_choice(settings, index);
+            let selected = source_index(&live_link.kind) == index;
+            let response = ui.add_enabled(choice.is_some(), egui::SelectableLabel::new(selected, *label));
+            if let Some(kind) = choice.filter(|_| response.clicked()) {
+                live_link.kind = kind;
+            }
+        }
+    });
+
+    let field = match &live_link.kind {
+        SourceKind::FileBridge(_) => Some(("File", &mut settings.bridge_path)),
+        SourceKind::XPlaneUdp(_) => Some(("Address", &mut settings.xplane_address)),
+        SourceKind::FlightGear(_) => Some(("UDP port", &mut settings.flightgear_port)),
+        SourceKind::FlightGearTelnet(_) => Some(("Address", &mut settings.flightgear_telnet)),
+        SourceKind::Replay(_) => Some(("File", &mut settings.replay_path)),
+    };
+    if let Some((label, text)) = field {
+        let response = ui.horizontal(|ui| {
+            ui.label(label);
+            ui.add(egui::TextEdit::singleline(text).desired_width(170.0))
+        }).inner;
+
+        // The source only switches once editing is finished.
+        match source_choice(settings, source_index(&live_link.kind)) {
+            Some(kind) if response.lost_focus() => live_link.kind = kind,
+            None => {
+                ui.label(egui::RichText::new(format!("Invalid {}", label.to_lowercase())).size(12.0).color(egui::Color32::from_rgb(255, 100, 100)));
+            }
+            _ => {}
+        }
+    }
+    if let SourceKind::FileBridge(_) = live_link.kind {
+        ui.label(egui::RichText::new(format!("Default matches the Lua script; set {} to move both", BRIDGE_PATH_VAR)).size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+    }
+
+    draw_connection_health(ui, live_link);
+
+    if let Some(capabilities) = live_link.capabilities {
+        let station = if capabilities.station_info { "station from sim" } else { "station looked up by frequency" };
+        let tuning = if capabilities.nav_tuning { "NAV1 tuning" } else { "no NAV1 tuning" };
+        ui.label(egui::RichText::new(format!("{} • {} • {:.0} Hz", tuning, station, capabilities.update_rate_hz)).size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+    }
+
+    if !matches!(live_link.kind, SourceKind::Replay(_)) {
+        ui.checkbox(&mut live_link.recording, format!("Record to {}", recording_path().display()));
+    }
+}
+
+fn draw_connection_health(ui: &mut egui::Ui, live_link: &LiveLink) {
+    let state_color = match live_link.state {
+        ConnectionState::Live => egui::Color32::from_rgb(100, 255, 100),
+        ConnectionState::Waiting => egui::Color32::from_rgb(255, 220, 100),
+        ConnectionState::Stale => egui::Color32::from_rgb(255, 160, 60),
+        ConnectionState::Disconnected => egui::Color32::from_rgb(150, 150, 150),
+        ConnectionState::Error(_) => egui::Color32::from_rgb(255, 100, 100),
+    };
+    ui.label(egui::RichText::new(live_link.state.label()).size(13.0).color(state_color));
+
+    if let Some(last_update) = live_link.last_update {
+        let age = SystemTime::now().duration_since(last_update).unwrap_or_default().as_secs_f64();
+        ui.label(egui::RichText::new(format!("Last update {} ({:.1} s ago) • {:.1} Hz", format_utc(last_update), age, live_link.data_rate_hz)).size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+    }
+    if live_link.state == ConnectionState::Stale {
+        ui.label(egui::RichText::new("Tracking paused until the aircraft moves again").size(12.0).color(state_color));
+    }
+}
+
+fn format_utc(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+    format!("{:02}:{:02}:{:02}Z", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+const SOURCE_LABELS: [&str; 5] = ["FlyWithLua file", "X-Plane UDP", "FlightGear UDP", "FlightGear telnet", "Replay"];
+
+fn source_index(kind: &SourceKind) -> usize {
+    match kind {
+        SourceKind::FileBridge(_) => 0,
+        SourceKind::XPlaneUdp(_) => 1,
+        SourceKind::FlightGear(_) => 2,
+        SourceKind::FlightGearTelnet(_) => 3,
+        SourceKind::Replay(_) => 4,
+    }
+}
+
+fn source_choice(settings: &SourceSettings, index: usize) -> Option<SourceKind> {
+    match index {
+        0 => settings.file_bridge(),
+        1 => settings.xplane_udp(),
+        2 => settings.flightgear(),
+        3 => settings.flightgear_telnet(),
+        _ => settings.replay(),
+    }
+}
+
+fn draw_rules_selector(ui: &mut egui::Ui, entry_rules: &mut EntryRules) {
+    ui.label(egui::RichText::new("Entry Rules:").size(15.0));
+    ui.add_space(6.0);
+
+    let custom_tolerance = match *entry_rules {
+        EntryRules::Custom { tolerance } => tolerance,
+        _ => 10.0,
+    };
+
+    egui::ComboBox::new("entry_rules", "")
+        .selected_text(entry_rules.label())
+        .width(200.0)
+        .show_ui(ui, |ui| {
+            for rules in [EntryRules::Faa, EntryRules::Icao, EntryRules::Jeppesen, EntryRules::Custom { tolerance: custom_tolerance }] {
+                let selected = std::mem::discriminant(entry_rules) == std::mem::discriminant(&rules);
+                if ui.selectable_label(selected, rules.label()).clicked() {
+                    *entry_rules = rules;
+                }
+            }
+        });
+
+    if let EntryRules::Custom { tolerance } = entry_rules {
+        ui.add(egui::Slider::new(tolerance, 0.0..=20.0).step_by(1.0).suffix("° tolerance"));
+    } else {
+        ui.label(egui::RichText::new(format!("±{:.0}° at sector boundaries", entry_rules.boundary_tolerance())).size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+    }
+}
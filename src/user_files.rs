@@ -0,0 +1,53 @@
+use std::env;
+use std::path::PathBuf;
+
+pub const USER_DIR_VAR: &str = "HOLDING_TRAINER_HOME";
+const APP_DIR_NAME: &str = "holding-trainer";
+
+// Where favourites and recordings live, so they don't depend on the directory the
+// app was started from: the override variable, else %APPDATA% (Windows) or
+// $XDG_CONFIG_HOME / ~/.config, and the working directory as a last resort.
+pub fn user_directory() -> PathBuf {
+    resolve_user_directory(|name| env::var(name).ok().filter(|value| !value.is_empty()), cfg!(windows))
+}
+
+fn resolve_user_directory(var: impl Fn(&str) -> Option<String>, windows: bool) -> PathBuf {
+    if let Some(path) = var(USER_DIR_VAR) {
+        return PathBuf::from(path);
+    }
+
+    let base = if windows {
+        var("APPDATA")
+            .map(PathBuf::from)
+            .or_else(|| var("USERPROFILE").map(|profile| PathBuf::from(profile).join("AppData").join("Roaming")))
+    } else {
+        var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map_or_else(|| PathBuf::from("."), |base| base.join(APP_DIR_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_the_override_then_the_platform_directory() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| pairs.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+
+        let everything = vars(&[(USER_DIR_VAR, "/srv/trainer"), ("HOME", "/home/ana"), ("APPDATA", "C:\\Users\\ana\\AppData\\Roaming")]);
+        assert_eq!(resolve_user_directory(everything, false), PathBuf::from("/srv/trainer"));
+
+        let home = vars(&[("HOME", "/home/ana")]);
+        assert_eq!(resolve_user_directory(home, false), PathBuf::from("/home/ana/.config/holding-trainer"));
+        let xdg = vars(&[("HOME", "/home/ana"), ("XDG_CONFIG_HOME", "/home/ana/cfg")]);
+        assert_eq!(resolve_user_directory(xdg, false), PathBuf::from("/home/ana/cfg/holding-trainer"));
+
+        let appdata = vars(&[("APPDATA", "C:\\Users\\ana\\AppData\\Roaming")]);
+        assert_eq!(resolve_user_directory(appdata, true), PathBuf::from("C:\\Users\\ana\\AppData\\Roaming").join(APP_DIR_NAME));
+        assert_eq!(resolve_user_directory(vars(&[]), true), PathBuf::from("."));
+    }
+}